@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use crate::sweep;
+use crate::wire::Wire;
+
+/// Closest meeting point by Manhattan distance from the origin, and the smallest combined
+/// signal delay over all meeting points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub distance: i32,
+    pub delay: i32
+}
+
+pub struct Circuit<'a> {
+    wires: &'a [Wire],
+    // For every point where different wires meet, the fewest steps each wire takes to get there.
+    delays: HashMap<(i32, i32), Vec<Option<i32>>>
+}
+
+impl<'a> Circuit<'a> {
+    pub fn new(wires: &'a [Wire]) -> Circuit<'a> {
+        let mut delays: HashMap<(i32, i32), Vec<Option<i32>>> = HashMap::new();
+        for crossing in sweep::crossings(wires).iter().filter(|crossing| !crossing.is_self_intersection()) {
+            let entry = delays.entry(crossing.point).or_insert_with(|| vec![None; wires.len()]);
+            for id in [crossing.first, crossing.second] {
+                let delay = wires[id.wire].intervals[id.index].delay_to(crossing.point);
+                entry[id.wire] = Some(entry[id.wire].map_or(delay, |d| d.min(delay)));
+            }
+        }
+        Circuit {wires, delays}
+    }
+
+    pub fn wires(&self) -> &[Wire] {
+        self.wires
+    }
+
+    /// Points visited by every wire in `selection`, with their combined delay.
+    pub fn meeting_points(&self, selection: &[usize]) -> Vec<((i32, i32), i32)> {
+        let mut result: Vec<((i32, i32), i32)> = self.delays
+            .iter()
+            .filter_map(|(&point, delays)| {
+                let delay: Option<i32> = selection.iter().map(|&wire| delays[wire]).sum();
+                delay.map(|delay| (point, delay))
+            })
+            .collect();
+        result.sort_unstable();
+        result
+    }
+
+    fn report(&self, selection: &[usize]) -> Option<Report> {
+        let points = self.meeting_points(selection);
+        let distance = points.iter().map(|((x, y), _)| x.abs() + y.abs()).min()?;
+        let delay = points.iter().map(|&(_, delay)| delay).min()?;
+        Some(Report {distance, delay})
+    }
+
+    pub fn pair(&self, first: usize, second: usize) -> Option<Report> {
+        self.report(&[first, second])
+    }
+
+    /// Report over the points that all wires pass through.
+    pub fn all(&self) -> Option<Report> {
+        let selection: Vec<usize> = (0..self.wires.len()).collect();
+        self.report(&selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AocResult;
+
+    #[test]
+    fn test_three_wires() -> AocResult<()> {
+        let wires = [Wire::new("R8,U5,L5,D3")?, Wire::new("U7,R6,D4,L4")?, Wire::new("U3,R6")?];
+        let circuit = Circuit::new(&wires);
+        assert_eq!(circuit.pair(0, 1), Some(Report {distance: 6, delay: 30}));
+        assert_eq!(circuit.pair(0, 2), Some(Report {distance: 6, delay: 26}));
+        assert_eq!(circuit.pair(1, 2), Some(Report {distance: 1, delay: 2}));
+        assert_eq!(circuit.meeting_points(&[0, 1, 2]), vec![((3, 3), 46)]);
+        assert_eq!(circuit.all(), Some(Report {distance: 6, delay: 46}));
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt::Write;
+use crate::circuit::Circuit;
+
+const COLORS: [&str; 6] = ["#1f77b4", "#d62728", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf"];
+
+/// Draws every wire as a polyline, with the points where wires meet as small circles and the
+/// points shared by all wires as larger ones. The y axis points up, as in the puzzle.
+pub fn render(circuit: &Circuit) -> String {
+    let mut paths = vec![];
+    for wire in circuit.wires() {
+        let mut points = vec![(0, 0)];
+        points.extend(wire.intervals.iter().map(|interval| interval.end()));
+        paths.push(points);
+    }
+    let all_points = paths.iter().flatten();
+    let min_x = all_points.clone().map(|p| p.0).min().unwrap_or(0);
+    let max_x = all_points.clone().map(|p| p.0).max().unwrap_or(0);
+    let min_y = all_points.clone().map(|p| p.1).min().unwrap_or(0);
+    let max_y = all_points.map(|p| p.1).max().unwrap_or(0);
+    let extent = (max_x - min_x).max(max_y - min_y).max(1);
+    let margin = extent / 50 + 1;
+    let stroke = extent as f64 / 800.0;
+
+    let mut result = String::new();
+    let _ = writeln!(result, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+                     min_x - margin, -max_y - margin, max_x - min_x + 2 * margin, max_y - min_y + 2 * margin);
+    for (i, path) in paths.iter().enumerate() {
+        let points: Vec<String> = path.iter().map(|(x, y)| format!("{},{}", x, -y)).collect();
+        let _ = writeln!(result, "  <polyline fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" points=\"{}\"/>",
+                         COLORS[i % COLORS.len()], stroke, points.join(" "));
+    }
+
+    let mut pairwise = vec![];
+    for first in 0..paths.len() {
+        for second in first + 1..paths.len() {
+            pairwise.extend(circuit.meeting_points(&[first, second]).into_iter().map(|(point, _)| point));
+        }
+    }
+    pairwise.sort_unstable();
+    pairwise.dedup();
+    for (x, y) in pairwise {
+        let _ = writeln!(result, "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"black\"/>", x, -y, stroke * 3.0);
+    }
+    if paths.len() > 2 {
+        let all: Vec<usize> = (0..paths.len()).collect();
+        for ((x, y), _) in circuit.meeting_points(&all) {
+            let _ = writeln!(result, "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"{}\"/>",
+                             x, -y, stroke * 8.0, stroke);
+        }
+    }
+    let _ = writeln!(result, "  <circle cx=\"0\" cy=\"0\" r=\"{}\" fill=\"gold\"/>", stroke * 5.0);
+    result.push_str("</svg>\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::Wire;
+    use crate::AocResult;
+
+    #[test]
+    fn test_render() -> AocResult<()> {
+        let wires = [Wire::new("R8,U5,L5,D3")?, Wire::new("U7,R6,D4,L4")?];
+        let svg = render(&Circuit::new(&wires));
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -8 10 9\">"));
+        assert!(svg.contains("points=\"0,0 8,0 8,-5 3,-5 3,-2\""));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert_eq!(svg.matches("fill=\"black\"").count(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::wire::{Interval, Orientation, Wire};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SegmentId {
+    pub wire: usize,
+    pub index: usize
+}
+
+/// A point shared by two segments, `first` < `second`. Collinear segments that overlap share
+/// every point of the overlap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Crossing {
+    pub point: (i32, i32),
+    pub first: SegmentId,
+    pub second: SegmentId
+}
+
+impl Crossing {
+    fn new(point: (i32, i32), a: SegmentId, b: SegmentId) -> Crossing {
+        Crossing {point, first: a.min(b), second: a.max(b)}
+    }
+
+    pub fn is_self_intersection(&self) -> bool {
+        self.first.wire == self.second.wire
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Event { Insert, Query, Remove }
+
+/// Horizontal against vertical segments: sweeps from left to right, keeping the horizontal
+/// segments under the sweep line ordered by y, and looks up the y range of every vertical one.
+fn perpendicular(segments: &[(SegmentId, &Interval)], result: &mut Vec<Crossing>) {
+    let mut events = Vec::new();
+    for (i, (_, interval)) in segments.iter().enumerate() {
+        let range = interval.get_range();
+        match interval.orientation {
+            Orientation::Horizontal => {
+                events.push((*range.start(), Event::Insert, i));
+                events.push((*range.end(), Event::Remove, i));
+            },
+            Orientation::Vertical => events.push((interval.fixed_coord, Event::Query, i)),
+            _ => {}
+        }
+    }
+    events.sort_unstable();
+
+    let mut active: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for (x, event, i) in events {
+        let (id, interval) = segments[i];
+        match event {
+            Event::Insert => active.entry(interval.fixed_coord).or_default().push(i),
+            Event::Remove => {
+                let ids = active.get_mut(&interval.fixed_coord).unwrap();
+                ids.retain(|&j| j != i);
+                if ids.is_empty() {
+                    active.remove(&interval.fixed_coord);
+                }
+            },
+            Event::Query => {
+                for (&y, ids) in active.range(interval.get_range()) {
+                    result.extend(ids.iter().map(|&j| Crossing::new((x, y), id, segments[j].0)));
+                }
+            }
+        }
+    }
+}
+
+/// Segments on the same line: sorted by where they start, each one overlaps those still
+/// active when it starts.
+fn collinear(segments: &[(SegmentId, &Interval)], result: &mut Vec<Crossing>) {
+    let mut lines: HashMap<(Orientation, i32), Vec<usize>> = HashMap::new();
+    for (i, (_, interval)) in segments.iter().enumerate() {
+        lines.entry((interval.orientation, interval.fixed_coord)).or_default().push(i);
+    }
+    for ids in lines.values_mut() {
+        ids.sort_unstable_by_key(|&i| *segments[i].1.get_range().start());
+        let mut active: Vec<usize> = Vec::new();
+        for &i in ids.iter() {
+            let (id, interval) = segments[i];
+            let range = interval.get_range();
+            active.retain(|&j| segments[j].1.get_range().end() >= range.start());
+            for &j in &active {
+                let (other_id, other) = segments[j];
+                let last = *range.end().min(other.get_range().end());
+                for coord in *range.start()..=last {
+                    result.push(Crossing::new(interval.point(coord), id, other_id));
+                }
+            }
+            active.push(i);
+        }
+    }
+}
+
+/// Diagonal segments against segments on any other kind of line: sweeps from left to right,
+/// checking every pair whose x ranges overlap. Pairs of axis-aligned segments are left to
+/// `perpendicular`.
+fn diagonal(segments: &[(SegmentId, &Interval)], result: &mut Vec<Crossing>) {
+    let mut order: Vec<usize> = (0..segments.len()).collect();
+    order.sort_unstable_by_key(|&i| *segments[i].1.x_range().start());
+    let mut active_diagonal: Vec<usize> = Vec::new();
+    let mut active_straight: Vec<usize> = Vec::new();
+    for i in order {
+        let (id, interval) = segments[i];
+        let start = *interval.x_range().start();
+        active_diagonal.retain(|&j| *segments[j].1.x_range().end() >= start);
+        active_straight.retain(|&j| *segments[j].1.x_range().end() >= start);
+        let straight: &[usize] = if interval.orientation.is_diagonal() { &active_straight } else { &[] };
+        for &j in active_diagonal.iter().chain(straight) {
+            let (other_id, other) = segments[j];
+            if let Some(point) = interval.find_intersection(other) {
+                result.push(Crossing::new(point, id, other_id));
+            }
+        }
+        if interval.orientation.is_diagonal() {
+            active_diagonal.push(i);
+        } else {
+            active_straight.push(i);
+        }
+    }
+}
+
+/// All points where segments of `wires` meet, including a wire meeting itself, except for the
+/// origin and the joints between consecutive segments of a wire.
+/// Perpendicular crossings take O((n + k) log n) for n segments and k crossings, but collinear
+/// and diagonal segments are compared pairwise whenever their ranges overlap, which is O(n²) in
+/// the worst case.
+pub fn crossings(wires: &[Wire]) -> Vec<Crossing> {
+    let segments: Vec<(SegmentId, &Interval)> = wires
+        .iter()
+        .enumerate()
+        .flat_map(|(wire, w)| w.intervals.iter().enumerate().map(move |(index, interval)| (SegmentId {wire, index}, interval)))
+        .collect();
+    let mut result = Vec::new();
+    perpendicular(&segments, &mut result);
+    collinear(&segments, &mut result);
+    if segments.iter().any(|(_, interval)| interval.orientation.is_diagonal()) {
+        diagonal(&segments, &mut result);
+    }
+
+    result.retain(|crossing| {
+        let (first, second) = (crossing.first, crossing.second);
+        let joint = first.wire == second.wire && first.index + 1 == second.index
+            && wires[first.wire].intervals[first.index].end() == crossing.point;
+        crossing.point != (0, 0) && !joint
+    });
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use std::time::Instant;
+
+    // Small deterministic generator, to avoid a dependency.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 33) % bound
+        }
+    }
+
+    fn random_wire(rng: &mut Lcg, segments: usize, max_step: u64, directions: &[&str]) -> Wire {
+        let moves: Vec<String> = (0..segments)
+            .map(|_| format!("{}{}", directions[rng.next(directions.len() as u64) as usize], rng.next(max_step) + 1))
+            .collect();
+        Wire::new(&moves.join(",")).unwrap()
+    }
+
+    const AXES: [&str; 4] = ["U", "D", "L", "R"];
+    const ALL: [&str; 8] = ["U", "D", "L", "R", "UR", "UL", "DR", "DL"];
+
+    // Every visited point, with the segments that visit it.
+    fn points(wires: &[Wire]) -> HashMap<(i32, i32), Vec<SegmentId>> {
+        let mut result: HashMap<(i32, i32), Vec<SegmentId>> = HashMap::new();
+        for (wire, w) in wires.iter().enumerate() {
+            for (index, interval) in w.intervals.iter().enumerate() {
+                for point in interval.get_range().map(|coord| interval.point(coord)) {
+                    result.entry(point).or_default().push(SegmentId {wire, index});
+                }
+            }
+        }
+        result
+    }
+
+    fn brute_force(wires: &[Wire]) -> HashSet<Crossing> {
+        let mut result = HashSet::new();
+        for (&point, ids) in &points(wires) {
+            for (i, &a) in ids.iter().enumerate() {
+                for &b in &ids[i + 1..] {
+                    result.insert(Crossing::new(point, a, b));
+                }
+            }
+        }
+        result.retain(|crossing| {
+            let (first, second) = (crossing.first, crossing.second);
+            let joint = first.wire == second.wire && first.index + 1 == second.index
+                && wires[first.wire].intervals[first.index].end() == crossing.point;
+            crossing.point != (0, 0) && !joint
+        });
+        result
+    }
+
+    #[test]
+    fn test_collinear() {
+        let wires = [Wire::new("R8,U2,L6").unwrap(), Wire::new("U2,R4,D4").unwrap()];
+        let crossings: Vec<(i32, i32)> = crossings(&wires).into_iter().map(|crossing| crossing.point).collect();
+        assert_eq!(crossings, vec![(2, 2), (3, 2), (4, 0), (4, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Lcg(7);
+        for _ in 0..200 {
+            let count = rng.next(3) as usize + 1;
+            let directions = if rng.next(2) == 0 { &AXES[..] } else { &ALL[..] };
+            let wires: Vec<Wire> = (0..count).map(|_| random_wire(&mut rng, 12, 6, directions)).collect();
+            let expected = brute_force(&wires);
+            let actual = crossings(&wires);
+            assert_eq!(actual.len(), expected.len());
+            assert_eq!(actual.into_iter().collect::<HashSet<_>>(), expected);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_large_wires() {
+        let mut rng = Lcg(2019);
+        let wires: Vec<Wire> = (0..4).map(|_| random_wire(&mut rng, 20_000, 100, &AXES)).collect();
+        let start = Instant::now();
+        let crossings = crossings(&wires);
+        println!("{} segments, {} crossings in {:?}", 4 * 20_000, crossings.len(), start.elapsed());
+    }
+}
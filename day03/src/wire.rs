@@ -0,0 +1,199 @@
+use std::error::Error;
+use std::ops::RangeInclusive;
+use crate::AocResult;
+
+/// Direction of a segment. Diagonal segments go up to the right (y - x is fixed) and
+/// anti-diagonal ones up to the left (x + y is fixed).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    AntiDiagonal
+}
+
+impl Orientation {
+    pub fn is_diagonal(self) -> bool {
+        self == Orientation::Diagonal || self == Orientation::AntiDiagonal
+    }
+}
+
+/// A straight piece of wire. `fixed_coord` identifies the line it lies on, `first_coord` and
+/// `second_coord` are the y coordinates of the ends of a vertical segment and the x
+/// coordinates of any other.
+#[derive(Clone, Debug)]
+pub struct Interval {
+    pub fixed_coord: i32,
+    pub first_coord: i32,
+    pub second_coord: i32,
+    pub start_time: i32,
+    pub orientation: Orientation
+}
+
+impl Interval {
+    fn between(start: (i32, i32), end: (i32, i32), start_time: i32) -> Interval {
+        let (orientation, fixed_coord, first_coord, second_coord) = if start.1 == end.1 {
+            (Orientation::Horizontal, start.1, start.0, end.0)
+        } else if start.0 == end.0 {
+            (Orientation::Vertical, start.0, start.1, end.1)
+        } else if end.1 - start.1 == end.0 - start.0 {
+            (Orientation::Diagonal, start.1 - start.0, start.0, end.0)
+        } else {
+            (Orientation::AntiDiagonal, start.0 + start.1, start.0, end.0)
+        };
+        Interval {fixed_coord, first_coord, second_coord, start_time, orientation}
+    }
+
+    pub fn get_range(&self) -> RangeInclusive<i32> {
+        if self.first_coord < self.second_coord {
+            self.first_coord ..= self.second_coord
+        } else {
+            self.second_coord ..= self.first_coord
+        }
+    }
+
+    pub fn x_range(&self) -> RangeInclusive<i32> {
+        match self.orientation {
+            Orientation::Vertical => self.fixed_coord ..= self.fixed_coord,
+            _ => self.get_range()
+        }
+    }
+
+    pub fn start(&self) -> (i32, i32) {
+        self.point(self.first_coord)
+    }
+
+    pub fn end(&self) -> (i32, i32) {
+        self.point(self.second_coord)
+    }
+
+    /// The point of the segment's line at the given coordinate, see `first_coord`.
+    pub fn point(&self, coord: i32) -> (i32, i32) {
+        match self.orientation {
+            Orientation::Horizontal => (coord, self.fixed_coord),
+            Orientation::Vertical => (self.fixed_coord, coord),
+            Orientation::Diagonal => (coord, self.fixed_coord + coord),
+            Orientation::AntiDiagonal => (coord, self.fixed_coord - coord)
+        }
+    }
+
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        match self.orientation {
+            Orientation::Vertical => x == self.fixed_coord && self.get_range().contains(&y),
+            _ => self.get_range().contains(&x) && self.point(x).1 == y
+        }
+    }
+
+    /// The grid point where two segments on different lines cross. Diagonals may cross between
+    /// grid points, which the wires never reach.
+    pub fn find_intersection(&self, other: &Interval) -> Option<(i32, i32)> {
+        use Orientation::*;
+        let (a, b) = if self.orientation <= other.orientation { (self, other) } else { (other, self) };
+        let point = match (a.orientation, b.orientation) {
+            (Horizontal, Vertical) => (b.fixed_coord, a.fixed_coord),
+            (Horizontal, Diagonal) => (a.fixed_coord - b.fixed_coord, a.fixed_coord),
+            (Horizontal, AntiDiagonal) => (b.fixed_coord - a.fixed_coord, a.fixed_coord),
+            (Vertical, Diagonal) | (Vertical, AntiDiagonal) => b.point(a.fixed_coord),
+            (Diagonal, AntiDiagonal) if (b.fixed_coord - a.fixed_coord) % 2 == 0 =>
+                a.point((b.fixed_coord - a.fixed_coord) / 2),
+            _ => return None
+        };
+        if a.contains(point) && b.contains(point) { Some(point) } else { None }
+    }
+
+    /// Steps the wire takes to reach a point of this interval. A diagonal step moves one unit
+    /// along both axes.
+    pub fn delay_to(&self, (x, y): (i32, i32)) -> i32 {
+        let (start_x, start_y) = self.start();
+        self.start_time + (x - start_x).abs().max((y - start_y).abs())
+    }
+}
+
+pub struct Wire {
+    pub intervals: Vec<Interval>
+}
+
+impl Wire {
+    /// Parses comma-separated moves like `R8` or `UR5`. Anything else is an error pointing at
+    /// the offending move.
+    pub fn new(input: &str) -> AocResult<Wire> {
+        let mut wire = Wire {intervals: Vec::new()};
+        let mut position: (i32, i32) = (0, 0);
+        let mut time = 0;
+        let mut column = 0;
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(wire);
+        }
+        for (index, raw) in input.split(',').enumerate() {
+            let token = raw.trim();
+            let start = column + raw.len() - raw.trim_start().len() + 1;
+            let error = || Box::<dyn Error>::from(format!("Invalid move {:?} (move {}, column {})", token, index + 1, start));
+            column += raw.len() + 1;
+
+            let split = token.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(token.len());
+            let (direction, step) = token.split_at(split);
+            let (dx, dy) = match direction {
+                "U" => (0, 1),
+                "D" => (0, -1),
+                "L" => (-1, 0),
+                "R" => (1, 0),
+                "UR" => (1, 1),
+                "UL" => (-1, 1),
+                "DR" => (1, -1),
+                "DL" => (-1, -1),
+                _ => return Err(error())
+            };
+            if step.is_empty() || !step.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(error());
+            }
+            let step: i32 = step.parse().map_err(|_| error())?;
+            // Positions and times have to stay within `i32`.
+            let next = position.0.checked_add(dx * step).zip(position.1.checked_add(dy * step)).ok_or_else(error)?;
+            wire.intervals.push(Interval::between(position, next, time));
+            position = next;
+            time = time.checked_add(step).ok_or_else(error)?;
+        }
+        Ok(wire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> AocResult<()> {
+        let wire = Wire::new("R2, UL3,DR1,D2")?;
+        let ends: Vec<(i32, i32)> = wire.intervals.iter().map(|interval| interval.end()).collect();
+        assert_eq!(ends, vec![(2, 0), (-1, 3), (0, 2), (0, 0)]);
+        assert_eq!(wire.intervals[1].orientation, Orientation::AntiDiagonal);
+        assert_eq!(wire.intervals[2].delay_to((0, 2)), 6);
+        assert!(Wire::new("")?.intervals.is_empty());
+
+        let error = |input| Wire::new(input).err().map(|e| e.to_string());
+        assert_eq!(error("R2,X5,U1"), Some("Invalid move \"X5\" (move 2, column 4)".to_owned()));
+        assert_eq!(error("R2, U"), Some("Invalid move \"U\" (move 2, column 5)".to_owned()));
+        assert_eq!(error("R2000000000,R2000000000"), Some("Invalid move \"R2000000000\" (move 2, column 13)".to_owned()));
+        // Turning back keeps the position in range, but not the time.
+        assert_eq!(error("R2000000000,L2000000000"), Some("Invalid move \"L2000000000\" (move 2, column 13)".to_owned()));
+        assert!(Wire::new("R2000000000,L147483647").is_ok());
+        assert_eq!(error("R2,U-1"), Some("Invalid move \"U-1\" (move 2, column 4)".to_owned()));
+        assert_eq!(error("R2,,U1"), Some("Invalid move \"\" (move 2, column 4)".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_diagonal_intersections() -> AocResult<()> {
+        let segment = |input| Wire::new(input).map(|wire| wire.intervals[0].clone());
+        let diagonal = segment("UR4")?;
+        assert_eq!(diagonal.find_intersection(&segment("U3")?), Some((0, 0)));
+        assert_eq!(Wire::new("R2,U4")?.intervals[1].find_intersection(&diagonal), Some((2, 2)));
+        assert_eq!(Wire::new("U3,R4")?.intervals[1].find_intersection(&diagonal), Some((3, 3)));
+        assert_eq!(Wire::new("R4,UL4")?.intervals[1].find_intersection(&diagonal), Some((2, 2)));
+        // These two only cross at (1.5, 1.5).
+        assert_eq!(Wire::new("R3,UL3")?.intervals[1].find_intersection(&diagonal), None);
+        assert_eq!(Wire::new("R5,U1")?.intervals[1].find_intersection(&diagonal), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use crate::AocResult;
+
+/// Group requirement for non-decreasing numbers: some digit repeated at least or exactly
+/// the given number of times in a row. `AtLeast(1)` accepts every non-decreasing number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group { AtLeast(usize), Exactly(usize) }
+
+impl Group {
+    /// Longest run length worth telling apart; longer runs count as this one.
+    fn cap(self) -> usize {
+        match self {
+            Group::AtLeast(size) | Group::Exactly(size) => size + 1
+        }
+    }
+
+    /// Whether a finished run of `run` equal digits satisfies the requirement.
+    fn closes(self, run: usize) -> bool {
+        match self {
+            Group::AtLeast(size) => run >= size,
+            Group::Exactly(size) => run == size
+        }
+    }
+
+    fn matches(self, digits: &[u8]) -> bool {
+        digits.windows(2).all(|pair| pair[0] <= pair[1])
+            && digits.chunk_by(|a, b| a == b).any(|run| self.closes(run.len()))
+    }
+}
+
+/// Parses a non-negative decimal number of any length into digits, dropping leading zeros.
+fn parse(number: &str) -> AocResult<Vec<u8>> {
+    let number = number.trim();
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Box::<dyn Error>::from(format!("Not a decimal number: {:?}", number)));
+    }
+    let digits: Vec<u8> = number.trim_start_matches('0').bytes().map(|b| b - b'0').collect();
+    Ok(if digits.is_empty() { vec![0] } else { digits })
+}
+
+fn compare(a: &[u8], b: &[u8]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Counts non-decreasing numbers with a `Group` digit by digit, remembering how many ways
+/// there are to finish a number from every (remaining length, last digit, run, satisfied) state.
+pub struct Counter {
+    group: Group,
+    memo: HashMap<(usize, u8, usize, bool), u128>
+}
+
+impl Counter {
+    pub fn new(group: Group) -> Counter {
+        Counter {group, memo: HashMap::new()}
+    }
+
+    fn ways(&mut self, remaining: usize, last: u8, run: usize, satisfied: bool) -> AocResult<u128> {
+        if remaining == 0 {
+            return Ok(u128::from(satisfied || self.group.closes(run)));
+        }
+        let key = (remaining, last, run, satisfied);
+        if let Some(&result) = self.memo.get(&key) {
+            return Ok(result);
+        }
+        let mut result: u128 = 0;
+        for digit in last..=9 {
+            let ways = self.next(remaining - 1, last, run, satisfied, digit)?;
+            result = result.checked_add(ways).ok_or("Count does not fit in 128 bits")?;
+        }
+        self.memo.insert(key, result);
+        Ok(result)
+    }
+
+    fn next(&mut self, remaining: usize, last: u8, run: usize, satisfied: bool, digit: u8) -> AocResult<u128> {
+        if digit == last {
+            self.ways(remaining, digit, (run + 1).min(self.group.cap()), satisfied)
+        } else {
+            let satisfied = satisfied || self.group.closes(run);
+            self.ways(remaining, digit, 1, satisfied)
+        }
+    }
+
+    /// Matching numbers in 0..=`limit`.
+    fn count_up_to(&mut self, limit: &[u8]) -> AocResult<u128> {
+        let mut result: u128 = 0;
+        let mut add = |value: u128| -> AocResult<()> {
+            result = result.checked_add(value).ok_or("Count does not fit in 128 bits")?;
+            Ok(())
+        };
+        // Shorter numbers, which are all below the limit.
+        for len in 1..limit.len() {
+            let first = if len == 1 { 0 } else { 1 };
+            for digit in first..=9 {
+                add(self.ways(len - 1, digit, 1, false)?)?;
+            }
+        }
+        // Numbers as long as the limit: follow its digits, branching off below each of them.
+        let mut last = if limit.len() == 1 { 0 } else { 1 };
+        let (mut run, mut satisfied) = (0, false);
+        for (i, &bound) in limit.iter().enumerate() {
+            for digit in last..bound {
+                let ways = if i == 0 {
+                    self.ways(limit.len() - 1, digit, 1, false)?
+                } else {
+                    self.next(limit.len() - i - 1, last, run, satisfied, digit)?
+                };
+                add(ways)?;
+            }
+            if bound < last {
+                return Ok(result);
+            }
+            if i > 0 && bound == last {
+                run = (run + 1).min(self.group.cap());
+            } else {
+                satisfied = satisfied || (i > 0 && self.group.closes(run));
+                run = 1;
+            }
+            last = bound;
+        }
+        add(u128::from(satisfied || self.group.closes(run)))?;
+        Ok(result)
+    }
+
+    /// Matching numbers between two decimal numbers of any length, inclusive.
+    pub fn count(&mut self, low: &str, high: &str) -> AocResult<u128> {
+        let (low, high) = (parse(low)?, parse(high)?);
+        if compare(&low, &high) == Ordering::Greater {
+            return Ok(0);
+        }
+        let below_low = self.count_up_to(&low)? - u128::from(self.group.matches(&low));
+        Ok(self.count_up_to(&high)? - below_low)
+    }
+}
+
+/// Lazily yields the matching numbers between `low` and `high`, in increasing order. Only
+/// non-decreasing numbers are visited.
+pub fn matches(low: &str, high: &str, group: Group) -> AocResult<impl Iterator<Item = String>> {
+    let (mut current, high) = (parse(low)?, parse(high)?);
+    // The smallest non-decreasing number not below `low`.
+    if let Some(i) = current.windows(2).position(|pair| pair[1] < pair[0]) {
+        let digit = current[i];
+        for d in &mut current[i + 1..] {
+            *d = digit;
+        }
+    }
+    let candidates = std::iter::from_fn(move || {
+        if compare(&current, &high) == Ordering::Greater {
+            return None;
+        }
+        let result = current.clone();
+        // Next non-decreasing number: bump the last digit below 9 and repeat it to the end.
+        match current.iter().rposition(|&d| d < 9) {
+            Some(i) => {
+                let digit = current[i] + 1;
+                for d in &mut current[i..] {
+                    *d = digit;
+                }
+            },
+            None => {
+                let len = current.len() + 1;
+                current = vec![1; len];
+            }
+        }
+        Some(result)
+    });
+    Ok(candidates
+        .filter(move |digits| group.matches(digits))
+        .map(|digits| digits.into_iter().map(|d| char::from(b'0' + d)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::solve;
+
+    #[test]
+    fn test_matches_solve() -> AocResult<()> {
+        let (mut part1, mut part2) = (Counter::new(Group::AtLeast(2)), Counter::new(Group::Exactly(2)));
+        for &(low, high) in &[(171309, 643603), (1, 99999), (111110, 111122), (5, 5), (100, 99)] {
+            let (low_str, high_str) = (low.to_string(), high.to_string());
+            let counts = (part1.count(&low_str, &high_str)? as i32, part2.count(&low_str, &high_str)? as i32);
+            assert_eq!(counts, solve(low, high), "{}..={}", low, high);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_rules() -> AocResult<()> {
+        let cases = [
+            (Group::AtLeast(1), Rules::new().non_decreasing()),
+            (Group::AtLeast(3), Rules::new().non_decreasing().has_group(3)),
+            (Group::Exactly(3), Rules::new().non_decreasing().exact_group(3))
+        ];
+        for (group, rules) in &cases {
+            let mut counter = Counter::new(*group);
+            for &(low, high) in &[(0u64, 0u64), (0, 9), (0, 1000), (7, 123456), (99, 1000), (111, 111)] {
+                let expected = rules.count(low..=high);
+                assert_eq!(counter.count(&low.to_string(), &high.to_string())?, u128::from(expected), "{:?} {}..={}", group, low, high);
+                let listed: Vec<String> = matches(&low.to_string(), &high.to_string(), *group)?.collect();
+                let expected: Vec<String> = (low..=high).filter(|&n| rules.matches(n)).map(|n| n.to_string()).collect();
+                assert_eq!(listed, expected);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_big_ranges() -> AocResult<()> {
+        // Non-decreasing numbers of length L with no zeros: C(L + 8, 8); summed for L = 1..=40.
+        let binomial = |n: u128, k: u128| (1..=k).fold(1u128, |acc, i| acc * (n + 1 - i) / i);
+        let nines = "9".repeat(40);
+        assert_eq!(Counter::new(Group::AtLeast(1)).count("1", &nines)?, binomial(49, 9) - 1);
+        assert!(Counter::new(Group::Exactly(2)).count("0", &"9".repeat(1000))? > 0);
+        let first: Vec<String> = matches("18446744073709551616", &nines, Group::Exactly(2))?.take(2).collect();
+        assert_eq!(first, vec!["18888888888888888899", "18899999999999999999"]);
+        assert!(Counter::new(Group::AtLeast(1)).count("12", "x").is_err());
+        Ok(())
+    }
+}
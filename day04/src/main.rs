@@ -1,5 +1,15 @@
+use std::env;
+use std::error::Error;
 use itertools::Itertools;
 
+mod dp;
+mod rules;
+
+use dp::{Counter, Group};
+use rules::Rules;
+
+type AocResult<T> = std::result::Result<T, Box<dyn Error>>;
+
 struct Properties {
     increasing: bool,
     has_group: bool,
@@ -12,18 +22,20 @@ fn digits(mut i: i32) -> impl Iterator<Item = i32>  {
             None
         } else {
             let next = i % 10;
-            i = i / 10;
+            i /= 10;
             Some(next)
         }
     })
 }
 
+/// Checks every number in the range, see `Rules::count` and `dp::Counter` for the fast ways.
+#[cfg_attr(not(test), allow(dead_code))]
 fn solve(low: i32, high: i32) -> (i32, i32) {
     let mut result = (0, 0);
     for candidate in low..=high {
         let mut props = Properties {increasing: true, has_group: false, has_group_of_two: false};
         let mut prev_key = 10;
-        for (key, group) in &digits(candidate).into_iter().group_by(|&d| d) {
+        for (key, group) in &digits(candidate).group_by(|&d| d) {
             let group_size = group.fold(0, |acc, _| acc + 1);
             if key > prev_key {
                 props.increasing = false;
@@ -51,6 +63,98 @@ fn solve(low: i32, high: i32) -> (i32, i32) {
     result
 }
 
-fn main() {
-    println!("{:?}", solve(171309, 643603));
+struct Options {
+    range: (String, String),
+    digit_sum: Option<(u32, u32)>,
+    divisor: Option<u64>,
+    list: usize
+}
+
+fn parse_pair<T: std::str::FromStr>(value: &str) -> AocResult<(T, T)>
+    where T::Err: Error + 'static
+{
+    match value.find('-') {
+        Some(i) => Ok((value[..i].parse()?, value[i + 1..].parse()?)),
+        None => Err(Box::from(format!("Expected MIN-MAX, got {}", value)))
+    }
+}
+
+/// `day4 [LOW HIGH | LOW-HIGH] [--digit-sum MIN-MAX] [--divisible-by N] [--list N]`, where the
+/// extra rules apply to both parts and `--list` prints the first N passwords of part 2.
+fn parse_options() -> AocResult<Options> {
+    let range = ("171309".to_owned(), "643603".to_owned());
+    let mut options = Options {range, digit_sum: None, divisor: None, list: 0};
+    let mut positional = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--digit-sum" => options.digit_sum = Some(parse_pair(&value()?)?),
+            "--divisible-by" => match value()?.parse()? {
+                0 => return Err(Box::from("--divisible-by must be positive")),
+                divisor => options.divisor = Some(divisor)
+            },
+            "--list" => options.list = value()?.parse()?,
+            _ if arg.starts_with("--") => return Err(Box::from(format!("Unknown argument {}", arg))),
+            _ => positional.push(arg)
+        }
+    }
+    options.range = match positional.as_slice() {
+        [] => options.range,
+        [range] => parse_pair(range)?,
+        [low, high] => (low.clone(), high.clone()),
+        _ => return Err(Box::from("Expected LOW HIGH or LOW-HIGH"))
+    };
+    Ok(options)
+}
+
+fn with_options(mut rules: Rules, options: &Options) -> Rules {
+    if let Some((min, max)) = options.digit_sum {
+        rules = rules.digit_sum(min..=max);
+    }
+    if let Some(divisor) = options.divisor {
+        rules = rules.predicate(move |n| n % divisor == 0);
+    }
+    rules
+}
+
+fn main() -> AocResult<()> {
+    let options = parse_options()?;
+    let (low, high) = &options.range;
+    let extra_rules = options.digit_sum.is_some() || options.divisor.is_some();
+    if extra_rules {
+        // Arbitrary rules need the numbers themselves, which limits the range to 64 bits.
+        let bound = |value: &str| value.parse::<u64>()
+            .map_err(|_| format!("--digit-sum and --divisible-by need a range within 64 bits, got {}", value));
+        let range = bound(low)?..=bound(high)?;
+        let part1 = with_options(Rules::new().non_decreasing().has_group(2), &options).count(range.clone());
+        let part2 = with_options(Rules::new().non_decreasing().exact_group(2), &options).count(range);
+        println!("{:?}", (part1, part2));
+    } else {
+        let part1 = Counter::new(Group::AtLeast(2)).count(low, high)?;
+        let part2 = Counter::new(Group::Exactly(2)).count(low, high)?;
+        println!("{:?}", (part1, part2));
+    }
+    // Listed passwords go through the same extra rules as the count.
+    let extra = with_options(Rules::new(), &options);
+    let passwords = dp::matches(low, high, Group::Exactly(2))?
+        .filter(|password| !extra_rules || password.parse().is_ok_and(|n| extra.matches(n)));
+    for password in passwords.take(options.list) {
+        println!("{}", password);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_solve() {
+        for &(low, high) in &[(171309, 643603), (1, 99999), (111110, 111122)] {
+            let part1 = Rules::new().non_decreasing().has_group(2).count(low as u64..=high as u64);
+            let part2 = Rules::new().non_decreasing().exact_group(2).count(low as u64..=high as u64);
+            assert_eq!(solve(low, high), (part1 as i32, part2 as i32));
+        }
+    }
 }
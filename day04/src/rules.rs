@@ -0,0 +1,148 @@
+use std::ops::RangeInclusive;
+
+/// Digits of `n`, most significant first.
+pub fn digits_of(mut n: u64) -> Vec<u8> {
+    let mut result = vec![(n % 10) as u8];
+    while n >= 10 {
+        n /= 10;
+        result.push((n % 10) as u8);
+    }
+    result.reverse();
+    result
+}
+
+/// Lengths of the runs of equal digits.
+fn groups(digits: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    let mut rest = digits;
+    std::iter::from_fn(move || {
+        let first = *rest.first()?;
+        let size = rest.iter().take_while(|&&d| d == first).count();
+        rest = &rest[size..];
+        Some(size)
+    })
+}
+
+type Check = Box<dyn Fn(u64, &[u8]) -> bool>;
+
+/// Password rules, all of which have to hold. Built by chaining, e.g.
+/// `Rules::new().non_decreasing().exact_group(2)`.
+#[derive(Default)]
+pub struct Rules {
+    non_decreasing: bool,
+    checks: Vec<Check>
+}
+
+impl Rules {
+    pub fn new() -> Rules {
+        Rules::default()
+    }
+
+    /// Digits never decrease from left to right.
+    pub fn non_decreasing(mut self) -> Rules {
+        self.non_decreasing = true;
+        self
+    }
+
+    /// Some digit repeats at least `size` times in a row.
+    pub fn has_group(self, size: usize) -> Rules {
+        self.check(move |_, digits| groups(digits).any(|group| group >= size))
+    }
+
+    /// Some digit repeats exactly `size` times in a row, not as part of a longer run.
+    pub fn exact_group(self, size: usize) -> Rules {
+        self.check(move |_, digits| groups(digits).any(|group| group == size))
+    }
+
+    pub fn digit_sum(self, range: RangeInclusive<u32>) -> Rules {
+        self.check(move |_, digits| range.contains(&digits.iter().map(|&d| u32::from(d)).sum()))
+    }
+
+    pub fn predicate<F: Fn(u64) -> bool + 'static>(self, predicate: F) -> Rules {
+        self.check(move |n, _| predicate(n))
+    }
+
+    fn check<F: Fn(u64, &[u8]) -> bool + 'static>(mut self, check: F) -> Rules {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    fn matches_digits(&self, n: u64, digits: &[u8]) -> bool {
+        (!self.non_decreasing || digits.windows(2).all(|pair| pair[0] <= pair[1]))
+            && self.checks.iter().all(|check| check(n, digits))
+    }
+
+    pub fn matches(&self, n: u64) -> bool {
+        self.matches_digits(n, &digits_of(n))
+    }
+
+    /// Number of matching passwords in `range`. With the non-decreasing rule only the
+    /// non-decreasing digit sequences are generated, C(len + 9, 9) per length instead of 10^len.
+    pub fn count(&self, range: RangeInclusive<u64>) -> u64 {
+        if !self.non_decreasing {
+            return range.filter(|&n| self.matches(n)).count() as u64;
+        }
+        let (low, high) = (*range.start(), *range.end());
+        if low > high {
+            return 0;
+        }
+        let mut result = 0;
+        let mut digits = vec![];
+        for len in digits_of(low).len()..=digits_of(high).len() {
+            self.count_sequences(&mut digits, len, &range, &mut result);
+        }
+        result
+    }
+
+    fn count_sequences(&self, digits: &mut Vec<u8>, len: usize, range: &RangeInclusive<u64>, result: &mut u64) {
+        if digits.len() == len {
+            let n = digits.iter().fold(0, |n, &d| n * 10 + u64::from(d));
+            if range.contains(&n) && self.matches_digits(n, digits) {
+                *result += 1;
+            }
+            return;
+        }
+        // Leading zeros only make sense for the number 0 itself.
+        let first = match digits.last() {
+            Some(&last) => last,
+            None if len == 1 => 0,
+            None => 1
+        };
+        for digit in first..=9 {
+            digits.push(digit);
+            self.count_sequences(digits, len, range, result);
+            digits.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let part2 = Rules::new().non_decreasing().exact_group(2);
+        assert!(part2.matches(112233));
+        assert!(!part2.matches(123444));
+        assert!(part2.matches(111122));
+        assert!(Rules::new().non_decreasing().has_group(2).matches(111111));
+        assert!(!Rules::new().non_decreasing().matches(223450));
+        assert!(Rules::new().digit_sum(10..=10).predicate(|n| n % 2 == 0).matches(1234));
+    }
+
+    #[test]
+    fn test_count() {
+        let rules = [
+            Rules::new().non_decreasing().has_group(2),
+            Rules::new().non_decreasing().exact_group(3).digit_sum(0..=20),
+            Rules::new().non_decreasing().predicate(|n| n % 7 == 0),
+            Rules::new().has_group(3)
+        ];
+        for rules in &rules {
+            for &(low, high) in &[(0, 0), (0, 999), (5, 12345), (98765, 100100), (10, 9)] {
+                let expected = (low..=high).filter(|&n| rules.matches(n)).count() as u64;
+                assert_eq!(rules.count(low..=high), expected, "{}..={}", low, high);
+            }
+        }
+    }
+}
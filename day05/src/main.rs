@@ -1,11 +1,14 @@
+use std::convert::TryFrom;
+use std::env;
 use std::error::Error;
+use std::fmt;
 use std::fs;
-use std::io::{BufReader, BufRead, Read, BufWriter, Write};
+use std::io::{self, BufReader, BufRead, Cursor, Read, BufWriter, Write};
 
-type AocResult<T> = std::result::Result<T, Box<Error>>;
+type AocResult<T> = std::result::Result<T, Box<dyn Error>>;
 
 macro_rules! err {
-    ($($tt:tt)*) => { Err(Box::<Error>::from(format!($($tt)*))) }
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
 }
 
 struct Intcode {
@@ -13,7 +16,9 @@ struct Intcode {
     pc: usize,
     input: BufReader<Box<dyn Read>>,
     output: BufWriter<Box<dyn Write>>,
-    halted: bool
+    halted: bool,
+    // Every output with the position of the instruction that produced it.
+    outputs: Vec<(usize, i32)>
 }
 
 #[derive(Debug)]
@@ -48,18 +53,19 @@ struct Instruction {
 }
 
 impl Intcode {
-    fn new(program: Vec<i32>, read: Box<Read>, write: Box<Write>) -> Intcode {
+    fn new(program: Vec<i32>, read: Box<dyn Read>, write: Box<dyn Write>) -> Intcode {
         Intcode {
             program,
             pc: 0,
             input: BufReader::new(read),
             output: BufWriter::new(write),
-            halted: false
+            halted: false,
+            outputs: Vec::new()
         }
     }
 
     fn parse(&self) -> AocResult<Instruction> {
-        let opcode = self.program[self.pc];
+        let opcode = self.read(self.pc)?;
         let (op, args_num) = match opcode % 100 {
             1 => (Op::Add, 3),
             2 => (Op::Mul, 3),
@@ -75,35 +81,35 @@ impl Intcode {
         let mut modes = opcode / 100;
         let mut args: Vec<Arg> = Vec::new();
         for arg_num in 0..args_num {
-            let value = self.program[self.pc + arg_num + 1];
+            let value = self.read(self.pc + arg_num + 1)?;
             let mode = match modes % 10 {
                 0 => Mode::Address,
                 1 => Mode::Immediate,
                 illegal => return err!("Illegal mode '{}'", illegal)
             };
             args.push(Arg {value, mode});
-            modes = modes / 10;
+            modes /= 10;
         }
         Ok(Instruction {op, args})
     }
 
     fn exec(&mut self) -> AocResult<()> {
-        while self.pc < self.program.len() && self.halted != true {
+        while self.pc < self.program.len() && !self.halted {
             let instruction = self.parse()?;
             let len = instruction.args.len();
             match instruction.op {
                 Op::Halt => self.halt(),
-                Op::Jit => self.jump_if_true(&instruction.args),
-                Op::Jif => self.jump_if_false(&instruction.args),
+                Op::Jit => self.jump_if_true(&instruction.args)?,
+                Op::Jif => self.jump_if_false(&instruction.args)?,
                 _ => {
                     self.pc += len + 1;
                     match instruction.op {
-                        Op::Add => self.add(&instruction.args),
-                        Op::Mul => self.mul(&instruction.args),
+                        Op::Add => self.add(&instruction.args)?,
+                        Op::Mul => self.mul(&instruction.args)?,
                         Op::Input => self.input(&instruction.args)?,
                         Op::Output => self.output(&instruction.args)?,
-                        Op::Lt => self.less(&instruction.args),
-                        Op::Eq => self.equal(&instruction.args),
+                        Op::Lt => self.less(&instruction.args)?,
+                        Op::Eq => self.equal(&instruction.args)?,
                         _ => unreachable!()
                     }
                 }
@@ -112,67 +118,93 @@ impl Intcode {
         Ok(())
     }
 
-    fn get_value(&self, arg: &Arg) -> i32 {
+    fn read(&self, address: usize) -> AocResult<i32> {
+        match self.program.get(address) {
+            Some(&value) => Ok(value),
+            None => err!("Invalid address {} at position {}", address, self.pc)
+        }
+    }
+
+    fn cell(&mut self, address: i32) -> AocResult<&mut i32> {
+        let pc = self.pc;
+        match usize::try_from(address) {
+            Ok(index) if index < self.program.len() => Ok(&mut self.program[index]),
+            _ => err!("Invalid address {} at position {}", address, pc)
+        }
+    }
+
+    fn get_value(&self, arg: &Arg) -> AocResult<i32> {
         match arg.mode {
-            Mode::Immediate => arg.value,
-            Mode::Address => self.program[arg.value as usize]
+            Mode::Immediate => Ok(arg.value),
+            Mode::Address => match usize::try_from(arg.value) {
+                Ok(address) => self.read(address),
+                Err(_) => err!("Invalid address {} at position {}", arg.value, self.pc)
+            }
+        }
+    }
+
+    fn arithmetic(&mut self, args: &[Arg], op: fn(i32, i32) -> Option<i32>) -> AocResult<()> {
+        match op(self.get_value(&args[0])?, self.get_value(&args[1])?) {
+            Some(value) => *self.cell(args[2].value)? = value,
+            None => return err!("Overflow at position {}", self.pc)
         }
+        Ok(())
     }
 
-    fn add(&mut self, args: &Vec<Arg>) {
-        self.program[args[2].value as usize] = self.get_value(&args[0]) + self.get_value(&args[1]);
+    fn add(&mut self, args: &[Arg]) -> AocResult<()> {
+        self.arithmetic(args, i32::checked_add)
     }
 
-    fn mul(&mut self, args: &Vec<Arg>) {
-        self.program[args[2].value as usize] = self.get_value(&args[0]) * self.get_value(&args[1]);
+    fn mul(&mut self, args: &[Arg]) -> AocResult<()> {
+        self.arithmetic(args, i32::checked_mul)
     }
 
-    fn input(&mut self, args: &Vec<Arg>) -> AocResult<()> {
+    fn input(&mut self, args: &[Arg]) -> AocResult<()> {
         let mut line = String::new();
         self.input.read_line(&mut line)?;
-        self.program[args[0].value as usize] = line.trim().parse()?;
+        *self.cell(args[0].value)? = line.trim().parse()?;
         Ok(())
     }
 
-    fn output(&mut self, args: &Vec<Arg>) -> AocResult<()> {
-        self.output.write(format!("{}\n", self.get_value(&args[0])).as_bytes())?;
+    fn output(&mut self, args: &[Arg]) -> AocResult<()> {
+        let value = self.get_value(&args[0])?;
+        self.outputs.push((self.pc - args.len() - 1, value));
+        writeln!(self.output, "{}", value)?;
         Ok(())
     }
 
-    fn jump_if_true(&mut self, args: &Vec<Arg>) {
-        if self.get_value(&args[0]) != 0 {
-            self.pc = self.get_value(&args[1]) as usize;
-        } else {
+    fn jump(&mut self, args: &[Arg], jump: bool) -> AocResult<()> {
+        if !jump {
             self.pc += 3;
+            return Ok(());
+        }
+        match self.get_value(&args[1])? {
+            target if target >= 0 => self.pc = target as usize,
+            target => return err!("Invalid jump target {} at position {}", target, self.pc)
         }
+        Ok(())
     }
 
-    fn jump_if_false(&mut self, args: &Vec<Arg>) {
-        if self.get_value(&args[0]) == 0 {
-            self.pc = self.get_value(&args[1]) as usize;
-        } else {
-            self.pc += 3;
-        }
+    fn jump_if_true(&mut self, args: &[Arg]) -> AocResult<()> {
+        let jump = self.get_value(&args[0])? != 0;
+        self.jump(args, jump)
     }
 
-    fn less(&mut self, args: &Vec<Arg>) {
-        let value;
-        if self.get_value(&args[0]) < self.get_value(&args[1]) {
-            value = 1;
-        } else {
-            value = 0;
-        }
-        self.program[args[2].value as usize] = value;
+    fn jump_if_false(&mut self, args: &[Arg]) -> AocResult<()> {
+        let jump = self.get_value(&args[0])? == 0;
+        self.jump(args, jump)
     }
 
-    fn equal(&mut self, args: &Vec<Arg>) {
-        let value;
-        if self.get_value(&args[0]) == self.get_value(&args[1]) {
-            value = 1;
-        } else {
-            value = 0;
-        }
-        self.program[args[2].value as usize] = value;
+    fn less(&mut self, args: &[Arg]) -> AocResult<()> {
+        let value = i32::from(self.get_value(&args[0])? < self.get_value(&args[1])?);
+        *self.cell(args[2].value)? = value;
+        Ok(())
+    }
+
+    fn equal(&mut self, args: &[Arg]) -> AocResult<()> {
+        let value = i32::from(self.get_value(&args[0])? == self.get_value(&args[1])?);
+        *self.cell(args[2].value)? = value;
+        Ok(())
     }
 
     fn halt(&mut self) {
@@ -180,12 +212,91 @@ impl Intcode {
     }
 }
 
+/// Result of the diagnostic program: how many checks passed before the final code.
+#[derive(Debug, PartialEq, Eq)]
+struct Diagnostic {
+    code: i32,
+    tests_passed: usize
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DiagnosticError {
+    /// Test number `test` (counting from 1) printed a non-zero value at instruction `pc`.
+    TestFailed { test: usize, pc: usize, value: i32 },
+    NoOutput,
+    Fault(String)
+}
+
+impl fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticError::TestFailed {test, pc, value} =>
+                write!(f, "Test {} failed at position {} with output {}", test, pc, value),
+            DiagnosticError::NoOutput => write!(f, "Diagnostic program produced no output"),
+            DiagnosticError::Fault(message) => write!(f, "Diagnostic program failed: {}", message)
+        }
+    }
+}
+
+impl Error for DiagnosticError {}
+
+/// Runs the TEST diagnostic program for the given system: every output but the last is a check
+/// that must be zero, the last one is the diagnostic code.
+fn diagnose(program: &[i32], system_id: i32) -> Result<Diagnostic, DiagnosticError> {
+    let input = Box::new(Cursor::new(format!("{}\n", system_id)));
+    let mut intcode = Intcode::new(program.to_vec(), input, Box::new(io::sink()));
+    intcode.exec().map_err(|e| DiagnosticError::Fault(e.to_string()))?;
+
+    let (&(_, code), checks) = intcode.outputs.split_last().ok_or(DiagnosticError::NoOutput)?;
+    if let Some((test, &(pc, value))) = checks.iter().enumerate().find(|(_, &(_, value))| value != 0) {
+        return Err(DiagnosticError::TestFailed {test: test + 1, pc, value});
+    }
+    Ok(Diagnostic {code, tests_passed: checks.len()})
+}
+
+const AIR_CONDITIONER: i32 = 1;
+const THERMAL_RADIATOR: i32 = 5;
+
 fn main() -> AocResult<()> {
     let input = fs::read_to_string("input.txt")?;
-    let program = input.split(',').map(|s| s.parse::<i32>()).collect::<Result<Vec<_>, _>>()?;
-    Intcode::new(
-        program,
-        Box::new(std::io::stdin()),
-        Box::new(std::io::stdout()),
-    ).exec()
-}
\ No newline at end of file
+    let program = input.trim().split(',').map(|s| s.parse::<i32>()).collect::<Result<Vec<_>, _>>()?;
+    // Without arguments, diagnose both systems; "--interactive" talks to the program directly.
+    let systems = match env::args().nth(1).as_deref() {
+        Some("--interactive") => {
+            return Intcode::new(program, Box::new(io::stdin()), Box::new(io::stdout())).exec();
+        },
+        Some(id) => vec![id.parse()?],
+        None => vec![AIR_CONDITIONER, THERMAL_RADIATOR]
+    };
+    for system_id in systems {
+        let diagnostic = diagnose(&program, system_id)?;
+        println!("{}", diagnostic.code);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose() -> AocResult<()> {
+        let input = fs::read_to_string("input.txt")?;
+        let program = input.trim().split(',').map(|s| s.parse::<i32>()).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(diagnose(&program, AIR_CONDITIONER)?, Diagnostic {code: 9006673, tests_passed: 9});
+        assert_eq!(diagnose(&program, THERMAL_RADIATOR)?, Diagnostic {code: 3629692, tests_passed: 0});
+        Ok(())
+    }
+
+    #[test]
+    fn test_failures() {
+        assert_eq!(diagnose(&[104, 0, 104, 3, 104, 7, 99], 1), Err(DiagnosticError::TestFailed {test: 2, pc: 2, value: 3}));
+        assert_eq!(diagnose(&[3, 0, 99], 1), Err(DiagnosticError::NoOutput));
+        assert!(matches!(diagnose(&[3, 0, 42], 1), Err(DiagnosticError::Fault(_))));
+        // Bad addresses, truncated instructions, overflows and bad jumps are faults, not panics.
+        for program in [&[4, 7, 99][..], &[4, -1, 99], &[3, 50, 99], &[1, 0, 0], &[1101, 1, 1, -3, 99],
+                        &[1102, 100000, 100000, 0, 99], &[1105, 1, -2], &[1108, 1, 1, 9, 99]] {
+            assert!(matches!(diagnose(program, 1), Err(DiagnosticError::Fault(_))), "{:?}", program);
+        }
+    }
+}
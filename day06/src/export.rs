@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use crate::orbit::{OrbitError, OrbitMap, COM};
+
+/// Bodies and links on the transfer path between two bodies, to draw differently.
+struct Highlight<'a> {
+    bodies: HashSet<&'a str>,
+    links: HashSet<(&'a str, &'a str)>
+}
+
+impl<'a> Highlight<'a> {
+    fn new(map: &'a OrbitMap, ends: Option<(&str, &str)>) -> Result<Highlight<'a>, OrbitError> {
+        let path = match ends {
+            Some((a, b)) => map.path(a, b)?,
+            None => vec![]
+        };
+        let mut links = HashSet::new();
+        for pair in path.windows(2) {
+            links.insert((pair[0], pair[1]));
+            links.insert((pair[1], pair[0]));
+        }
+        Ok(Highlight {bodies: path.into_iter().collect(), links})
+    }
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Graphviz digraph with an edge from every body to the ones orbiting it.
+pub fn dot(map: &OrbitMap, highlight: Option<(&str, &str)>) -> Result<String, OrbitError> {
+    let highlight = Highlight::new(map, highlight)?;
+    let mut result = String::from("digraph orbits {\n  rankdir=LR;\n  node [shape=circle];\n");
+    let mut stack = vec![COM];
+    while let Some(body) = stack.pop() {
+        if highlight.bodies.contains(body) {
+            let _ = writeln!(result, "  {} [color=red, fontcolor=red];", quote(body));
+        }
+        for child in map.children(body)? {
+            let style = if highlight.links.contains(&(body, child)) { " [color=red, penwidth=3]" } else { "" };
+            let _ = writeln!(result, "  {} -> {}{};", quote(body), quote(child), style);
+            stack.push(child);
+        }
+    }
+    result.push_str("}\n");
+    Ok(result)
+}
+
+/// `{"name": "COM", "orbiters": 13, "children": [...]}`, nested all the way down.
+pub fn json(map: &OrbitMap) -> Result<String, OrbitError> {
+    let mut result = String::new();
+    // `None` closes the body opened before its children; the flag tells whether a body comes
+    // first among its siblings.
+    let mut stack = vec![Some((COM, true))];
+    while let Some(entry) = stack.pop() {
+        let (body, first) = match entry {
+            Some(entry) => entry,
+            None => {
+                result.push_str("]}");
+                continue;
+            }
+        };
+        if !first {
+            result.push(',');
+        }
+        let _ = write!(result, "{{\"name\":{},\"orbiters\":{},\"children\":[", quote(body), map.orbiters(body)?);
+        stack.push(None);
+        for (i, child) in map.children(body)?.into_iter().enumerate().rev() {
+            stack.push(Some((child, i == 0)));
+        }
+    }
+    Ok(result)
+}
+
+/// Indented tree with the number of orbiters of every body; bodies on the highlighted
+/// transfer path are marked with `*`.
+pub fn ascii_tree(map: &OrbitMap, highlight: Option<(&str, &str)>) -> Result<String, OrbitError> {
+    let highlight = Highlight::new(map, highlight)?;
+    let mut result = String::new();
+    // Body, the prefix of its line, and whether it is the last of its siblings.
+    let mut stack = vec![(COM, String::new(), true)];
+    while let Some((body, prefix, last)) = stack.pop() {
+        let root = body == COM;
+        let branch = if root { "" } else if last { "└── " } else { "├── " };
+        let mark = if highlight.bodies.contains(body) { " *" } else { "" };
+        let _ = writeln!(result, "{}{}{} ({}){}", prefix, branch, body, map.orbiters(body)?, mark);
+        let children = map.children(body)?;
+        let prefix = if root { String::new() } else if last { format!("{}    ", prefix) } else { format!("{}│   ", prefix) };
+        for (i, child) in children.iter().enumerate().rev() {
+            stack.push((child, prefix.clone(), i + 1 == children.len()));
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN\n";
+
+    #[test]
+    fn test_export() -> Result<(), OrbitError> {
+        let map = OrbitMap::parse("COM)B\nB)C\nB)D\nD)E")?;
+        assert_eq!(ascii_tree(&map, Some(("C", "E")))?, "COM (4)\n└── B (3) *\n    ├── C (0) *\n    └── D (1) *\n        └── E (0) *\n");
+        assert!(ascii_tree(&map, None)?.contains("    ├── C (0)\n    └── D (1)\n"));
+        assert_eq!(json(&map)?, concat!(
+            "{\"name\":\"COM\",\"orbiters\":4,\"children\":[{\"name\":\"B\",\"orbiters\":3,\"children\":[",
+            "{\"name\":\"C\",\"orbiters\":0,\"children\":[]},",
+            "{\"name\":\"D\",\"orbiters\":1,\"children\":[{\"name\":\"E\",\"orbiters\":0,\"children\":[]}]}]}]}"
+        ));
+
+        let example = OrbitMap::parse(EXAMPLE)?;
+        let graph = dot(&example, Some(("YOU", "SAN")))?;
+        assert!(graph.starts_with("digraph orbits {"));
+        assert_eq!(graph.matches(" -> ").count(), 13);
+        assert_eq!(graph.matches("penwidth").count(), 6);
+        assert!(graph.contains("\"D\" -> \"I\" [color=red, penwidth=3];"));
+        assert!(graph.contains("\"COM\" -> \"B\";"));
+        assert!(dot(&example, Some(("YOU", "X"))).is_err());
+
+        // Deep enough to overflow the stack if rendered recursively.
+        let chain = |depth: usize| {
+            let lines: Vec<String> = (0..depth).map(|i| format!("B{})B{}", i, i + 1)).collect();
+            OrbitMap::parse(&format!("COM)B0\n{}", lines.join("\n")))
+        };
+        assert!(json(&chain(200_000)?)?.ends_with(&"]}".repeat(200_002)));
+        assert_eq!(ascii_tree(&chain(5_000)?, None)?.lines().count(), 5_002);
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+pub const COM: &str = "COM";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OrbitError {
+    Syntax { line: usize, text: String },
+    MissingCom,
+    MultipleParents { body: String, parents: (String, String) },
+    /// Bodies orbiting each other in a circle, each one orbited by the next.
+    Cycle(Vec<String>),
+    /// A body that orbits nothing, but is not COM.
+    Detached(String),
+    UnknownBody(String),
+    /// A transfer from or to COM, which orbits nothing.
+    NoOrbit(String)
+}
+
+impl fmt::Display for OrbitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrbitError::Syntax {line, text} => write!(f, "Line {}: expected A)B, got {:?}", line, text),
+            OrbitError::MissingCom => write!(f, "No {} in the map", COM),
+            OrbitError::MultipleParents {body, parents} =>
+                write!(f, "{} orbits both {} and {}", body, parents.0, parents.1),
+            OrbitError::Cycle(bodies) => write!(f, "Orbit cycle: {}", bodies.join(" -> ")),
+            OrbitError::Detached(body) => write!(f, "{} orbits nothing and is not {}", body, COM),
+            OrbitError::UnknownBody(body) => write!(f, "Unknown body {}", body),
+            OrbitError::NoOrbit(body) => write!(f, "{} orbits nothing", body)
+        }
+    }
+}
+
+impl Error for OrbitError {}
+
+/// A validated orbit tree rooted at COM.
+pub struct OrbitMap {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    depths: Vec<usize>,
+    orbiters: Vec<usize>
+}
+
+impl OrbitMap {
+    /// Parses lines like `COM)B`, meaning that B orbits COM.
+    pub fn parse(input: &str) -> Result<OrbitMap, OrbitError> {
+        let mut pairs = vec![];
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.split_once(')') {
+                Some((center, body)) if !center.is_empty() && !body.is_empty() && !body.contains(')') =>
+                    pairs.push((center, body)),
+                _ => return Err(OrbitError::Syntax {line: i + 1, text: line.to_owned()})
+            }
+        }
+        OrbitMap::from_pairs(pairs)
+    }
+
+    pub fn from_pairs<'a, I>(pairs: I) -> Result<OrbitMap, OrbitError>
+        where I: IntoIterator<Item = (&'a str, &'a str)>
+    {
+        let mut map = OrbitMap {names: vec![], ids: HashMap::new(), parents: vec![], children: vec![], depths: vec![], orbiters: vec![]};
+        for (center, body) in pairs {
+            let (center, body) = (map.add(center), map.add(body));
+            match map.parents[body] {
+                Some(parent) if parent == center => continue,
+                Some(parent) => return Err(OrbitError::MultipleParents {
+                    body: map.names[body].clone(),
+                    parents: (map.names[parent].clone(), map.names[center].clone())
+                }),
+                None => {}
+            }
+            map.parents[body] = Some(center);
+            map.children[center].push(body);
+        }
+        map.validate()?;
+        Ok(map)
+    }
+
+    fn add(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        self.parents.push(None);
+        self.children.push(vec![]);
+        id
+    }
+
+    /// Computes depths from COM; whatever COM does not reach is either on a cycle, orbits one,
+    /// or hangs off another root. COM itself orbiting anything can only be a cycle through it.
+    fn validate(&mut self) -> Result<(), OrbitError> {
+        let com = *self.ids.get(COM).ok_or(OrbitError::MissingCom)?;
+        let mut depths = vec![None; self.names.len()];
+        depths[com] = Some(0);
+        let mut queue = VecDeque::from(vec![com]);
+        let mut order = vec![];
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            for &child in &self.children[id] {
+                if depths[child].is_none() {
+                    depths[child] = Some(depths[id].unwrap() + 1);
+                    queue.push_back(child);
+                }
+            }
+        }
+        let unreached = depths.iter().position(Option::is_none);
+        if let Some(start) = self.parents[com].map(|_| com).or(unreached) {
+            let mut seen = HashSet::new();
+            let mut chain = vec![];
+            let mut id = start;
+            while seen.insert(id) {
+                chain.push(id);
+                match self.parents[id] {
+                    Some(parent) => id = parent,
+                    None => return Err(OrbitError::Detached(self.names[id].clone()))
+                }
+            }
+            let cycle_start = chain.iter().position(|&c| c == id).unwrap();
+            // Each body orbited by the next, starting where the walk entered the cycle.
+            let mut cycle: Vec<String> = chain[cycle_start..].iter().rev().map(|&c| self.names[c].clone()).collect();
+            cycle.rotate_right(1);
+            return Err(OrbitError::Cycle(cycle));
+        }
+        self.depths = depths.into_iter().map(Option::unwrap).collect();
+        // Deepest first, so that every body is done before the one it orbits.
+        self.orbiters = vec![0; self.names.len()];
+        for &id in order.iter().rev() {
+            if let Some(parent) = self.parents[id] {
+                self.orbiters[parent] += self.orbiters[id] + 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn id(&self, name: &str) -> Result<usize, OrbitError> {
+        self.ids.get(name).copied().ok_or_else(|| OrbitError::UnknownBody(name.to_owned()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn parent(&self, name: &str) -> Result<Option<&str>, OrbitError> {
+        Ok(self.parents[self.id(name)?].map(|parent| self.names[parent].as_str()))
+    }
+
+    pub fn children(&self, name: &str) -> Result<Vec<&str>, OrbitError> {
+        Ok(self.children[self.id(name)?].iter().map(|&child| self.names[child].as_str()).collect())
+    }
+
+    /// Number of direct and indirect orbits of a body, i.e. its distance from COM.
+    pub fn depth(&self, name: &str) -> Result<usize, OrbitError> {
+        Ok(self.depths[self.id(name)?])
+    }
+
+    /// Number of bodies orbiting a body, directly or not.
+    pub fn orbiters(&self, name: &str) -> Result<usize, OrbitError> {
+        Ok(self.orbiters[self.id(name)?])
+    }
+
+    pub fn total_orbits(&self) -> usize {
+        self.depths.iter().sum()
+    }
+
+    fn lca_id(&self, mut a: usize, mut b: usize) -> usize {
+        while self.depths[a] > self.depths[b] {
+            a = self.parents[a].unwrap();
+        }
+        while self.depths[b] > self.depths[a] {
+            b = self.parents[b].unwrap();
+        }
+        while a != b {
+            a = self.parents[a].unwrap();
+            b = self.parents[b].unwrap();
+        }
+        a
+    }
+
+    /// The deepest body that both bodies orbit, directly or not, or are.
+    pub fn lca(&self, a: &str, b: &str) -> Result<&str, OrbitError> {
+        Ok(&self.names[self.lca_id(self.id(a)?, self.id(b)?)])
+    }
+
+    /// Bodies from `a` to `b` through their common ancestor, both ends included.
+    pub fn path(&self, a: &str, b: &str) -> Result<Vec<&str>, OrbitError> {
+        let (a, b) = (self.id(a)?, self.id(b)?);
+        let lca = self.lca_id(a, b);
+        let up = |mut id: usize| {
+            let mut result = vec![];
+            while id != lca {
+                result.push(id);
+                id = self.parents[id].unwrap();
+            }
+            result
+        };
+        let mut result = up(a);
+        result.push(lca);
+        result.extend(up(b).into_iter().rev());
+        Ok(result.into_iter().map(|id| self.names[id].as_str()).collect())
+    }
+
+    /// Orbital transfers needed to move from the body `a` orbits to the body `b` orbits.
+    pub fn transfers(&self, a: &str, b: &str) -> Result<usize, OrbitError> {
+        let center = |name: &str| self.parents[self.id(name)?].ok_or_else(|| OrbitError::NoOrbit(name.to_owned()));
+        let (a, b) = (center(a)?, center(b)?);
+        let lca = self.lca_id(a, b);
+        Ok(self.depths[a] + self.depths[b] - 2 * self.depths[lca])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN\n";
+
+    #[test]
+    fn test_queries() -> Result<(), OrbitError> {
+        let map = OrbitMap::parse(EXAMPLE)?;
+        assert_eq!(map.len(), 14);
+        assert_eq!(map.total_orbits() - map.depth("YOU")? - map.depth("SAN")?, 42);
+        assert_eq!(map.depth("L")?, 7);
+        assert_eq!(map.orbiters("COM")?, 13);
+        assert_eq!(map.orbiters("E")?, 5);
+        assert_eq!(map.parent("COM")?, None);
+        assert_eq!(map.children("B")?, vec!["C", "G"]);
+        assert_eq!(map.lca("YOU", "SAN")?, "D");
+        assert_eq!(map.lca("H", "H")?, "H");
+        assert_eq!(map.path("YOU", "SAN")?, vec!["YOU", "K", "J", "E", "D", "I", "SAN"]);
+        assert_eq!(map.path("COM", "C")?, vec!["COM", "B", "C"]);
+        assert_eq!(map.transfers("YOU", "SAN")?, 4);
+        assert_eq!(map.transfers("K", "YOU")?, 1);
+        assert_eq!(map.transfers("YOU", "K")?, 1);
+        assert_eq!(map.transfers("L", "YOU")?, 0);
+        assert_eq!(map.transfers("COM", "YOU"), Err(OrbitError::NoOrbit("COM".to_owned())));
+        assert_eq!(map.depth("X"), Err(OrbitError::UnknownBody("X".to_owned())));
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |input| OrbitMap::parse(input).err();
+        assert_eq!(error("COM)B\nB)C\nA)C"), Some(OrbitError::MultipleParents {
+            body: "C".to_owned(), parents: ("B".to_owned(), "A".to_owned())
+        }));
+        assert_eq!(error("A)B\nB)C"), Some(OrbitError::MissingCom));
+        assert_eq!(error("COM)B\nX)Y\nY)Z\nZ)X"), Some(OrbitError::Cycle(vec!["X".to_owned(), "Y".to_owned(), "Z".to_owned()])));
+        assert_eq!(error("COM)A\nA)COM"), Some(OrbitError::Cycle(vec!["COM".to_owned(), "A".to_owned()])));
+        assert_eq!(error("COM)B\nA)C"), Some(OrbitError::Detached("A".to_owned())));
+        assert_eq!(error("COM)B\nB-C"), Some(OrbitError::Syntax {line: 2, text: "B-C".to_owned()}));
+        assert!(OrbitMap::parse("COM)B\nCOM)B").is_ok());
+    }
+}
@@ -1,12 +1,17 @@
+use std::env;
 use std::error::Error;
 use std::fs;
 use std::sync::mpsc::{Sender, Receiver, channel};
 use std::thread;
 
-type AocResult<T> = std::result::Result<T, Box<Error>>;
+mod search;
+
+use search::Best;
+
+type AocResult<T> = std::result::Result<T, Box<dyn Error>>;
 
 macro_rules! err {
-    ($($tt:tt)*) => { Err(Box::<Error>::from(format!($($tt)*))) }
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
 }
 
 struct Intcode {
@@ -77,13 +82,13 @@ impl Intcode {
                 illegal => return err!("Illegal mode '{}'", illegal)
             };
             args.push(Arg {value, mode});
-            modes = modes / 10;
+            modes /= 10;
         }
         Ok(Instruction {op, args})
     }
 
     fn exec(&mut self) -> AocResult<()> {
-        while self.pc < self.program.len() && self.halted != true {
+        while self.pc < self.program.len() && !self.halted {
             let instruction = self.parse()?;
             let len = instruction.args.len();
             match instruction.op {
@@ -114,27 +119,25 @@ impl Intcode {
         }
     }
 
-    fn add(&mut self, args: &Vec<Arg>) {
+    fn add(&mut self, args: &[Arg]) {
         self.program[args[2].value as usize] = self.get_value(&args[0]) + self.get_value(&args[1]);
     }
 
-    fn mul(&mut self, args: &Vec<Arg>) {
+    fn mul(&mut self, args: &[Arg]) {
         self.program[args[2].value as usize] = self.get_value(&args[0]) * self.get_value(&args[1]);
     }
 
-    fn input(&mut self, args: &Vec<Arg>) -> AocResult<()> {
+    fn input(&mut self, args: &[Arg]) -> AocResult<()> {
         self.program[args[0].value as usize] = self.input.recv()?;
-//        println!("Input: {}", self.program[args[0].value as usize]);
         Ok(())
     }
 
-    fn output(&mut self, args: &Vec<Arg>) -> AocResult<()> {
-//        println!("Output: {}", self.get_value(&args[0]));
+    fn output(&mut self, args: &[Arg]) -> AocResult<()> {
         self.output.send(self.get_value(&args[0]))?;
         Ok(())
     }
 
-    fn jump<F>(&mut self, args: &Vec<Arg>, cond: F)
+    fn jump<F>(&mut self, args: &[Arg], cond: F)
         where F: Fn(&i32, &i32) -> bool
     {
         if cond(&self.get_value(&args[0]), &0) {
@@ -144,13 +147,10 @@ impl Intcode {
         }
     }
 
-    fn cmp<F>(&mut self, args: &Vec<Arg>, cmp: F)
+    fn cmp<F>(&mut self, args: &[Arg], cmp: F)
         where F: Fn(&i32, &i32) -> bool
     {
-        let value = match cmp(&self.get_value(&args[0]), &self.get_value(&args[1])) {
-            true => 1,
-            false => 0
-        };
+        let value = i32::from(cmp(&self.get_value(&args[0]), &self.get_value(&args[1])));
         self.program[args[2].value as usize] = value;
     }
 
@@ -159,84 +159,126 @@ impl Intcode {
     }
 }
 
-fn permutations(seq: &Vec<i32>) -> Vec<Vec<i32>> {
-    if seq.len() == 1 {
-        return vec![seq.clone()]
+/// Runs the amplifiers in a chain, feeding 0 to the first one.
+fn series(program: &[i32], phases: &[i32]) -> AocResult<i32> {
+    let (sender, mut receiver) = channel();
+    sender.send(0)?;
+    for &phase in phases {
+        let (new_sender, new_receiver) = channel();
+        let (phase_sender, phase_receiver) = channel();
+        phase_sender.send(phase)?;
+        phase_sender.send(receiver.recv()?)?;
+        let mut intcode = Intcode::new(program.to_vec(), phase_receiver, new_sender);
+        receiver = new_receiver;
+        intcode.exec()?;
     }
-    let mut result = Vec::new();
-    for i in 0..seq.len() {
-        let mut base = seq.clone();
-        let elem = base.remove(i);
-        for mut perm in permutations(&base) {
-            perm.push(elem);
-            result.push(perm);
-        }
+    Ok(receiver.recv()?)
+}
+
+/// Runs the amplifiers in a loop, each in its own thread, until they all halt.
+fn feedback(program: &[i32], phases: &[i32]) -> AocResult<i32> {
+    let mut senders = vec![];
+    let mut receivers = vec![];
+    for &phase in phases {
+        let (sender, receiver) = channel();
+        sender.send(phase)?;
+        senders.push(sender);
+        receivers.push(receiver);
+    }
+    senders.first().ok_or("No amplifiers")?.send(0)?;
+    // Every amplifier writes to the input of the next one, and the last one to the first.
+    senders.rotate_left(1);
+
+    let mut intcodes = receivers.into_iter().zip(senders).map(|(r, s)| Intcode::new(program.to_vec(), r, s));
+    let mut first = intcodes.next().ok_or("No amplifiers")?;
+    let handles: Vec<_> = intcodes
+        .map(|mut intcode| thread::spawn(move || intcode.exec().map_err(|e| e.to_string())))
+        .collect();
+    first.exec()?;
+    for handle in handles {
+        handle.join().map_err(|_| "Amplifier panicked")??;
     }
-    result
+    // The first amplifier halts before the last one sends its final signal.
+    Ok(first.input.recv()?)
 }
 
-fn part1(program: &Vec<i32>) -> AocResult<i32> {
-    let mut result = std::i32::MIN;
-    for perm in permutations(&(0..5).collect()) {
-        let (sender, mut receiver) = channel();
-        sender.send(perm[0])?;
-        sender.send(0)?;
+struct Options {
+    series_phases: Vec<i32>,
+    feedback_phases: Vec<i32>,
+    threads: usize
+}
 
-        for i in 0..5 {
-            let (new_sender, new_receiver) = channel();
-            if i != 4 {
-                new_sender.send(perm[i + 1])?;
-            }
-            let mut intcode = Intcode::new(program.clone(), receiver, new_sender);
-            receiver = new_receiver;
-            intcode.exec()?;
-        }
+fn parse_program(input: &str) -> AocResult<Vec<i32>> {
+    Ok(input.trim().split(',').map(|s| s.parse::<i32>()).collect::<Result<Vec<_>, _>>()?)
+}
 
-        let output = receiver.recv()?;
-        if output > result {
-            result = output;
-        }
-    }
-    Ok(result)
+fn parse_phases(value: &str) -> AocResult<Vec<i32>> {
+    Ok(value.split(',').map(|s| s.trim().parse::<i32>()).collect::<Result<Vec<_>, _>>()?)
 }
 
-fn part2(program: &Vec<i32>) -> AocResult<i32> {
-    let mut result = std::i32::MIN;
-    for perm in permutations(&(5..10).collect()) {
-        let mut senders = vec![];
-        let mut receivers = vec![];
-        for phase in perm {
-            let (sender, receiver) = channel();
-            sender.send(phase)?;
-            senders.push(sender);
-            receivers.push(receiver);
-        }
-        receivers.rotate_right(1);
-        senders[4].send(0)?;
-
-        let mut intcodes: Vec<Intcode> = receivers
-            .into_iter()
-            .zip(senders)
-            .map(|(r, s)| Intcode::new(program.clone(), r, s))
-            .collect();
-        intcodes.rotate_left(1);
-
-        for (i, mut intcode) in intcodes.into_iter().enumerate() {
-            if i != 4 {
-                thread::spawn(move || intcode.exec().unwrap());
-            } else {
-                intcode.exec()?;
-                result = std::cmp::max(result, intcode.input.recv()?);
-            }
+fn parse_options() -> AocResult<Options> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut options = Options {series_phases: (0..5).collect(), feedback_phases: (5..10).collect(), threads};
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--series-phases" => options.series_phases = parse_phases(&value()?)?,
+            "--feedback-phases" => options.feedback_phases = parse_phases(&value()?)?,
+            "--threads" => options.threads = value()?.parse()?,
+            _ => return Err(Box::from(format!("Unknown argument {}", arg)))
         }
     }
-    Ok(result)
+    Ok(options)
+}
+
+fn part1(program: &[i32], options: &Options) -> AocResult<Best> {
+    search::best_phases(&options.series_phases, options.threads, |phases| series(program, phases))
+}
+
+fn part2(program: &[i32], options: &Options) -> AocResult<Best> {
+    search::best_phases(&options.feedback_phases, options.threads, |phases| feedback(program, phases))
+}
+
+fn report(best: &Best) {
+    let phases: Vec<String> = best.phases.iter().map(|phase| phase.to_string()).collect();
+    println!("{} (phases {})", best.signal, phases.join(","));
 }
 
 fn main() -> AocResult<()> {
+    let options = parse_options()?;
     let input = fs::read_to_string("input.txt")?;
-    let program = input.split(',').map(|s| s.parse::<i32>()).collect::<Result<Vec<_>, _>>()?;
-    println!("{:?}", part1(&program)?);
-    println!("{:?}", part2(&program)?);
+    let program = parse_program(&input)?;
+    report(&part1(&program, &options)?);
+    report(&part2(&program, &options)?);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(threads: usize) -> Options {
+        Options {series_phases: (0..5).collect(), feedback_phases: (5..10).collect(), threads}
+    }
+
+    #[test]
+    fn test_series() -> AocResult<()> {
+        let program = parse_program("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0")?;
+        let best = part1(&program, &options(3))?;
+        assert_eq!(best, Best {signal: 43210, phases: vec![4, 3, 2, 1, 0]});
+        // Three amplifiers with unusual phases.
+        let best = search::best_phases(&[7, 2, 9], 2, |phases| series(&program, phases))?;
+        assert_eq!(best, Best {signal: 972, phases: vec![9, 7, 2]});
+        Ok(())
+    }
+
+    #[test]
+    fn test_feedback() -> AocResult<()> {
+        let program = parse_program("3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5")?;
+        let best = part2(&program, &options(4))?;
+        assert_eq!(best.signal, 139629729);
+        assert_eq!(best.phases, vec![9, 8, 7, 6, 5]);
+        Ok(())
+    }
+}
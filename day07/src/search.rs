@@ -0,0 +1,127 @@
+use std::error::Error;
+use std::thread;
+use crate::AocResult;
+
+/// Calls `f` with every permutation of `items`, rearranging them in place (Heap's algorithm).
+/// `items` ends up in some permutation of its original order.
+pub fn for_each_permutation<F>(items: &mut [i32], mut f: F) -> AocResult<()>
+    where F: FnMut(&[i32]) -> AocResult<()>
+{
+    // Like the loop counters of the recursive version, one per level.
+    let mut counters = [0usize; 32];
+    if items.len() > counters.len() {
+        return Err(Box::<dyn Error>::from(format!("Too many items: {}", items.len())));
+    }
+    f(items)?;
+    let mut i = 1;
+    while i < items.len() {
+        if counters[i] < i {
+            let j = if i % 2 == 0 { 0 } else { counters[i] };
+            items.swap(j, i);
+            f(items)?;
+            counters[i] += 1;
+            i = 1;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Best {
+    pub signal: i32,
+    pub phases: Vec<i32>
+}
+
+impl Best {
+    // Ties go to the lexicographically smallest phases, so the result does not depend on
+    // the order in which the threads finish.
+    fn update(current: &mut Option<Best>, signal: i32, phases: &[i32]) {
+        let better = match current {
+            Some(best) => signal > best.signal || (signal == best.signal && phases < &best.phases[..]),
+            None => true
+        };
+        if better {
+            *current = Some(Best {signal, phases: phases.to_vec()});
+        }
+    }
+}
+
+/// Finds the order of `phases`, one per amplifier, that maximizes `signal`. Orders are split
+/// between `threads` workers by their first phase.
+pub fn best_phases<F>(phases: &[i32], threads: usize, signal: F) -> AocResult<Best>
+    where F: Fn(&[i32]) -> AocResult<i32> + Sync
+{
+    if phases.is_empty() {
+        return Err(Box::from("No phases to search"));
+    }
+    let threads = threads.clamp(1, phases.len());
+    let signal = &signal;
+    let results: Vec<Result<Option<Best>, String>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| scope.spawn(move || {
+                let mut best = None;
+                for first in (worker..phases.len()).step_by(threads) {
+                    let mut order = phases.to_vec();
+                    order.swap(0, first);
+                    let (head, rest) = order.split_at_mut(1);
+                    let mut sequence = head.to_vec();
+                    for_each_permutation(rest, |rest| {
+                        sequence.truncate(1);
+                        sequence.extend_from_slice(rest);
+                        Best::update(&mut best, signal(&sequence)?, &sequence);
+                        Ok(())
+                    }).map_err(|e| e.to_string())?;
+                }
+                Ok(best)
+            }))
+            .collect();
+        workers.into_iter().map(|worker| worker.join().unwrap_or_else(|_| Err("Worker panicked".to_owned()))).collect()
+    });
+
+    let mut best = None;
+    for result in results {
+        if let Some(candidate) = result? {
+            Best::update(&mut best, candidate.signal, &candidate.phases);
+        }
+    }
+    best.ok_or_else(|| Box::from("No phases to search"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_permutations() -> AocResult<()> {
+        for n in 0..7 {
+            let mut items: Vec<i32> = (0..n).collect();
+            let mut seen = HashSet::new();
+            let mut count = 0;
+            for_each_permutation(&mut items, |permutation| {
+                seen.insert(permutation.to_vec());
+                count += 1;
+                Ok(())
+            })?;
+            let factorial: usize = (1..=n as usize).product();
+            assert_eq!((count, seen.len()), (factorial, factorial));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_best_phases() -> AocResult<()> {
+        // Rewards putting larger phases first, weighted by position.
+        let signal = |phases: &[i32]| Ok(phases.iter().enumerate().map(|(i, &p)| p * (10 - i as i32)).sum());
+        for threads in 1..5 {
+            let best = best_phases(&[3, 7, 1, 5], threads, signal)?;
+            assert_eq!(best, Best {signal: 7 * 10 + 5 * 9 + 3 * 8 + 7, phases: vec![7, 5, 3, 1]});
+        }
+        let tied = best_phases(&[2, 1, 0], 2, |_| Ok(0))?;
+        assert_eq!(tied.phases, vec![0, 1, 2]);
+        Ok(())
+    }
+}
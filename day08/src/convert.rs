@@ -0,0 +1,282 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use crate::image::{SpaceImage, BLACK, TRANSPARENT, WHITE};
+
+type AocResult<T> = std::result::Result<T, Box<dyn Error>>;
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+/// RGB colours for black, white and transparent pixels, plus one for any other digit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub black: [u8; 3],
+    pub white: [u8; 3],
+    pub transparent: [u8; 3],
+    pub other: [u8; 3]
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette {black: [0, 0, 0], white: [255, 255, 255], transparent: [128, 128, 128], other: [255, 0, 0]}
+    }
+}
+
+impl Palette {
+    /// Parses `BLACK,WHITE[,TRANSPARENT[,OTHER]]` with colours as `rrggbb` hex.
+    pub fn parse(spec: &str) -> AocResult<Palette> {
+        let mut colors = vec![];
+        for hex in spec.split(',') {
+            let hex = hex.trim().trim_start_matches('#');
+            let value = u32::from_str_radix(hex, 16)?;
+            if hex.len() != 6 {
+                return err!("Expected rrggbb, got {:?}", hex);
+            }
+            colors.push([(value >> 16) as u8, (value >> 8) as u8, value as u8]);
+        }
+        let mut palette = Palette::default();
+        match colors.as_slice() {
+            [black, white, rest @ ..] if rest.len() <= 2 => {
+                palette.black = *black;
+                palette.white = *white;
+                if let Some(transparent) = rest.first() {
+                    palette.transparent = *transparent;
+                }
+                if let Some(other) = rest.get(1) {
+                    palette.other = *other;
+                }
+                Ok(palette)
+            },
+            _ => err!("Expected 2 to 4 colours, got {}", colors.len())
+        }
+    }
+
+    fn rgb(&self, color: u8) -> [u8; 3] {
+        match color {
+            BLACK => self.black,
+            WHITE => self.white,
+            TRANSPARENT => self.transparent,
+            _ => self.other
+        }
+    }
+
+    fn gray(&self, color: u8) -> u8 {
+        let [r, g, b] = self.rgb(color);
+        ((299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b)) / 1000) as u8
+    }
+}
+
+/// One plane of colours, such as the merged image or a single layer, enlarged so that every
+/// pixel becomes a `scale` x `scale` square.
+pub struct Plane<'a> {
+    pub colors: &'a [u8],
+    pub width: usize,
+    pub scale: usize
+}
+
+impl<'a> Plane<'a> {
+    pub fn merged(image: &SpaceImage, merged: &'a [u8], scale: usize) -> Plane<'a> {
+        Plane {colors: merged, width: image.width(), scale}
+    }
+
+    pub fn layer(image: &'a SpaceImage, index: usize, scale: usize) -> Plane<'a> {
+        Plane {colors: &image.layers()[index], width: image.width(), scale}
+    }
+
+    fn scaled_width(&self) -> usize {
+        self.width * self.scale
+    }
+
+    fn scaled_height(&self) -> usize {
+        self.colors.len() / self.width * self.scale
+    }
+
+    fn rows<T: Copy, F: Fn(u8) -> T>(&self, pixel: F) -> Vec<Vec<T>> {
+        let mut result = vec![];
+        for row in self.colors.chunks(self.width) {
+            let scaled: Vec<T> = row.iter().flat_map(|&color| vec![pixel(color); self.scale]).collect();
+            for _ in 0..self.scale {
+                result.push(scaled.clone());
+            }
+        }
+        result
+    }
+}
+
+/// Plain (P1) portable bitmap, where 1 is ink, i.e. a black pixel, so that it looks the same as
+/// the PNG with the default palette. Transparent pixels are left blank.
+pub fn pbm(plane: &Plane) -> String {
+    let mut result = format!("P1\n{} {}\n", plane.scaled_width(), plane.scaled_height());
+    for row in plane.rows(|color| if color == BLACK { "1" } else { "0" }) {
+        result.push_str(&row.join(" "));
+        result.push('\n');
+    }
+    result
+}
+
+/// Binary (P5) portable graymap, with the palette converted to gray.
+pub fn pgm(plane: &Plane, palette: &Palette) -> Vec<u8> {
+    let mut result = format!("P5\n{} {}\n255\n", plane.scaled_width(), plane.scaled_height()).into_bytes();
+    result.extend(plane.rows(|color| palette.gray(color)).concat());
+    result
+}
+
+/// RGBA PNG, where transparent pixels are see-through.
+pub fn write_png(plane: &Plane, palette: &Palette, path: &Path) -> AocResult<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, plane.scaled_width() as u32, plane.scaled_height() as u32);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let rows = plane.rows(|color| {
+        let [r, g, b] = palette.rgb(color);
+        [r, g, b, if color == TRANSPARENT { 0 } else { 255 }]
+    });
+    writer.write_image_data(&rows.concat().concat())?;
+    Ok(())
+}
+
+/// Reads a plain (P1) or raw (P4) portable bitmap as a single-layer image, ink becoming black.
+pub fn import_pbm(data: &[u8]) -> AocResult<SpaceImage> {
+    // Header tokens, skipping comments.
+    let mut position = 0;
+    let mut next_token = |data: &[u8]| -> AocResult<String> {
+        loop {
+            while position < data.len() && data[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            if position < data.len() && data[position] == b'#' {
+                while position < data.len() && data[position] != b'\n' {
+                    position += 1;
+                }
+                continue;
+            }
+            let start = position;
+            while position < data.len() && !data[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            if start == position {
+                return err!("Truncated PBM header");
+            }
+            return Ok(String::from_utf8_lossy(&data[start..position]).into_owned());
+        }
+    };
+    let magic = next_token(data)?;
+    let width: usize = next_token(data)?.parse()?;
+    let height: usize = next_token(data)?.parse()?;
+    let size = match width.checked_mul(height) {
+        Some(size) => size,
+        None => return err!("PBM dimensions {} x {} are too large", width, height)
+    };
+
+    let pixels: Vec<u8> = match magic.as_str() {
+        "P1" => data[position..]
+            .iter()
+            .filter(|b| !b.is_ascii_whitespace())
+            .map(|&b| match b {
+                b'0' => Ok(WHITE),
+                b'1' => Ok(BLACK),
+                other => err!("Unexpected {:?} in PBM data", char::from(other))
+            })
+            .take(size)
+            .collect::<AocResult<_>>()?,
+        "P4" => {
+            // A single whitespace byte separates the header from the packed rows.
+            let rows = &data[(position + 1).min(data.len())..];
+            let stride = width.div_ceil(8);
+            if stride.checked_mul(height).is_none_or(|length| rows.len() < length) {
+                return err!("Truncated PBM data");
+            }
+            (0..height)
+                .flat_map(|y| (0..width).map(move |x| (y, x)))
+                .map(|(y, x)| if rows[y * stride + x / 8] & (0x80 >> (x % 8)) != 0 { BLACK } else { WHITE })
+                .collect()
+        },
+        other => return err!("Not a PBM file: {:?}", other)
+    };
+    if pixels.len() != size {
+        return err!("Expected {} pixels, got {}", size, pixels.len());
+    }
+    Ok(SpaceImage::new(width, height, vec![pixels])?)
+}
+
+/// Spreads a single-layer image over `count` layers so that the merged image stays the same.
+/// Pixel `i` is resolved on layer `i % count`, with transparent pixels above it and its inverse
+/// colour on every layer below, so a decoder that merges layers in the wrong order shows the
+/// picture inverted rather than by chance correct.
+pub fn layered(image: &SpaceImage, count: usize) -> AocResult<SpaceImage> {
+    if count == 0 {
+        return err!("Need at least one layer");
+    }
+    let merged = image.merged();
+    let mut layers = vec![vec![TRANSPARENT; merged.len()]; count];
+    for (i, &color) in merged.iter().enumerate() {
+        let inverse = match color {
+            BLACK => WHITE,
+            WHITE => BLACK,
+            other => other
+        };
+        let depth = i % count;
+        layers[depth][i] = color;
+        for layer in &mut layers[depth + 1..] {
+            layer[i] = inverse;
+        }
+    }
+    Ok(SpaceImage::new(image.width(), image.height(), layers)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_export() -> AocResult<()> {
+        let image = SpaceImage::decode("0222112222120000", 2, 2)?;
+        let merged = image.merged();
+        assert_eq!(pbm(&Plane::merged(&image, &merged, 1)), "P1\n2 2\n1 0\n0 1\n");
+        assert_eq!(pbm(&Plane::layer(&image, 0, 1)), "P1\n2 2\n1 0\n0 0\n");
+        assert_eq!(pgm(&Plane::merged(&image, &merged, 2), &Palette::default()),
+                   [&b"P5\n4 4\n255\n"[..], &[0, 0, 255, 255, 0, 0, 255, 255, 255, 255, 0, 0, 255, 255, 0, 0]].concat());
+
+        let palette = Palette::parse("000080,ffff00")?;
+        assert_eq!((palette.black, palette.white, palette.transparent), ([0, 0, 128], [255, 255, 0], [128, 128, 128]));
+        assert!(Palette::parse("000000").is_err());
+        assert!(Palette::parse("000000,fff").is_err());
+
+        let path = env::temp_dir().join(format!("day8-test-{}.png", std::process::id()));
+        write_png(&Plane::merged(&image, &merged, 3), &palette, &path)?;
+        let (info, _) = png::Decoder::new(File::open(&path)?).read_info()?;
+        fs::remove_file(&path)?;
+        assert_eq!((info.width, info.height), (6, 6));
+        Ok(())
+    }
+
+    #[test]
+    fn test_import() -> AocResult<()> {
+        let plain = import_pbm(b"P1\n# comment\n3 2\n0 1 0\n1 1 0\n")?;
+        assert_eq!(plain.encode(), "101001");
+        let raw = import_pbm(&[&b"P4 3 2\n"[..], &[0b0100_0000, 0b1100_0000]].concat())?;
+        assert_eq!(raw, plain);
+        assert!(import_pbm(b"P1\n3 2\n0 1").is_err());
+        assert!(import_pbm(b"P2\n3 2\n").is_err());
+        assert!(import_pbm(b"P1 99999999999 99999999999\n0").is_err());
+        assert!(import_pbm(b"P4 99999999999 9999999999\n\0").is_err());
+
+        let image = SpaceImage::decode("0222112222120000", 2, 2)?;
+        let merged = image.merged();
+        assert_eq!(import_pbm(pbm(&Plane::merged(&image, &merged, 1)).as_bytes())?.merged(), merged);
+
+        let spread = layered(&raw, 4)?;
+        assert_eq!(spread.encode(), "122202002211011210010010");
+        let decoded = SpaceImage::decode(&spread.encode(), 3, 2)?;
+        assert_eq!(decoded.layers().len(), 4);
+        assert_eq!(decoded.merged(), raw.merged());
+        assert!(layered(&raw, 0).is_err());
+        Ok(())
+    }
+}
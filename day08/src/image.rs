@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::fmt;
+
+pub const BLACK: u8 = 0;
+pub const WHITE: u8 = 1;
+pub const TRANSPARENT: u8 = 2;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SifError {
+    EmptyDimensions,
+    NoLayers,
+    /// The data does not split into whole layers.
+    BadLength { length: usize, layer_size: usize },
+    BadDigit { position: usize, found: char }
+}
+
+impl fmt::Display for SifError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SifError::EmptyDimensions => write!(f, "Image width and height must be positive"),
+            SifError::NoLayers => write!(f, "Image has no layers"),
+            SifError::BadLength {length, layer_size} =>
+                write!(f, "{} digits do not make whole layers of {}", length, layer_size),
+            SifError::BadDigit {position, found} => write!(f, "Unexpected {:?} at position {}", found, position)
+        }
+    }
+}
+
+impl Error for SifError {}
+
+/// An image in the Space Image Format: layers of `width` x `height` digits, front layer first.
+/// Every digit is a colour, of which 0 is black, 1 is white and 2 is transparent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpaceImage {
+    width: usize,
+    height: usize,
+    layers: Vec<Vec<u8>>
+}
+
+impl SpaceImage {
+    pub fn new(width: usize, height: usize, layers: Vec<Vec<u8>>) -> Result<SpaceImage, SifError> {
+        if width == 0 || height == 0 {
+            return Err(SifError::EmptyDimensions);
+        }
+        if layers.is_empty() {
+            return Err(SifError::NoLayers);
+        }
+        for (i, layer) in layers.iter().enumerate() {
+            if layer.len() != width * height {
+                let length = i * width * height + layer.len();
+                return Err(SifError::BadLength {length, layer_size: width * height});
+            }
+            if let Some(position) = layer.iter().position(|&digit| digit > 9) {
+                let found = char::from_digit(u32::from(layer[position]), 36).unwrap_or('?');
+                return Err(SifError::BadDigit {position: i * width * height + position, found});
+            }
+        }
+        Ok(SpaceImage {width, height, layers})
+    }
+
+    /// Decodes a string of digits; surrounding whitespace is ignored.
+    pub fn decode(data: &str, width: usize, height: usize) -> Result<SpaceImage, SifError> {
+        if width == 0 || height == 0 {
+            return Err(SifError::EmptyDimensions);
+        }
+        let data = data.trim();
+        let mut digits = Vec::with_capacity(data.len());
+        for (position, ch) in data.chars().enumerate() {
+            match ch.to_digit(10) {
+                Some(digit) => digits.push(digit as u8),
+                None => return Err(SifError::BadDigit {position, found: ch})
+            }
+        }
+        let layer_size = width * height;
+        if digits.is_empty() || digits.len() % layer_size != 0 {
+            return Err(SifError::BadLength {length: digits.len(), layer_size});
+        }
+        let layers = digits.chunks(layer_size).map(|layer| layer.to_vec()).collect();
+        SpaceImage::new(width, height, layers)
+    }
+
+    pub fn encode(&self) -> String {
+        self.layers.iter().flatten().map(|&digit| char::from(b'0' + digit)).collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn layers(&self) -> &[Vec<u8>] {
+        &self.layers
+    }
+
+    /// The visible image: every pixel takes the colour of the frontmost layer that is not
+    /// transparent there, and stays transparent if all of them are.
+    pub fn merged(&self) -> Vec<u8> {
+        let mut merged = vec![TRANSPARENT; self.width * self.height];
+        for layer in &self.layers {
+            for (pixel, &color) in merged.iter_mut().zip(layer) {
+                if *pixel == TRANSPARENT {
+                    *pixel = color;
+                }
+            }
+        }
+        merged
+    }
+
+    /// Rows of the visible image, with white pixels set.
+    pub fn bitmap(&self) -> Vec<Vec<bool>> {
+        self.merged().chunks(self.width).map(|row| row.iter().map(|&color| color == WHITE).collect()).collect()
+    }
+
+    /// The visible image as text: white is `O`, black a space, transparent `.`, and any other
+    /// colour its digit.
+    pub fn render(&self) -> String {
+        self.merged()
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|&color| match color {
+                        BLACK => ' ',
+                        WHITE => 'O',
+                        TRANSPARENT => '.',
+                        digit => char::from(b'0' + digit)
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codec() -> Result<(), SifError> {
+        let image = SpaceImage::decode("123456789012\n", 3, 2)?;
+        assert_eq!(image.layers(), &[vec![1, 2, 3, 4, 5, 6], vec![7, 8, 9, 0, 1, 2]]);
+        assert_eq!(image.encode(), "123456789012");
+        assert_eq!(SpaceImage::decode("1234567", 3, 2), Err(SifError::BadLength {length: 7, layer_size: 6}));
+        assert_eq!(SpaceImage::decode("", 3, 2), Err(SifError::BadLength {length: 0, layer_size: 6}));
+        assert_eq!(SpaceImage::decode("12x456", 3, 2), Err(SifError::BadDigit {position: 2, found: 'x'}));
+        assert_eq!(SpaceImage::decode("1", 0, 1), Err(SifError::EmptyDimensions));
+        assert_eq!(SpaceImage::new(1, 1, vec![vec![1], vec![]]), Err(SifError::BadLength {length: 1, layer_size: 1}));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge() -> Result<(), SifError> {
+        let image = SpaceImage::decode("0222112222120000", 2, 2)?;
+        assert_eq!(image.merged(), vec![0, 1, 1, 0]);
+        assert_eq!(image.render(), " O\nO ");
+        assert_eq!(image.bitmap(), vec![vec![false, true], vec![true, false]]);
+        let image = SpaceImage::decode("2272", 2, 1)?;
+        assert_eq!(image.render(), "7.");
+        Ok(())
+    }
+}
@@ -1,65 +1,149 @@
-use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use day8::convert::{self, Palette, Plane};
+use day8::image::SpaceImage;
+use day8::stats::{self, Checksum};
 
-type AocResult<T> = std::result::Result<T, Box<std::error::Error>>;
+type AocResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-const W: usize = 25;
-const H: usize = 6;
+struct Options {
+    input: String,
+    width: usize,
+    height: usize,
+    png: Option<PathBuf>,
+    pbm: Option<PathBuf>,
+    pgm: Option<PathBuf>,
+    layers: Option<PathBuf>,
+    scale: usize,
+    palette: Palette,
+    import: Option<PathBuf>,
+    import_layers: usize,
+    checksums: Vec<Checksum>,
+    report: bool
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            input: "input.txt".to_owned(),
+            width: 25,
+            height: 6,
+            png: None,
+            pbm: None,
+            pgm: None,
+            layers: None,
+            scale: 1,
+            palette: Palette::default(),
+            import: None,
+            import_layers: 1,
+            checksums: vec![],
+            report: false
+        }
+    }
+}
 
-fn counter<I, T>(it: I) -> HashMap<T, i32>
-where
-    I: Iterator<Item=T>,
-    T: std::cmp::Eq + std::hash::Hash
-{
-    let mut result = HashMap::new();
-    for item in it {
-        let count = result.entry(item).or_insert(0);
-        *count += 1;
+fn parse_options() -> AocResult<Options> {
+    let mut options = Options::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--input" => options.input = value()?,
+            "--width" => options.width = value()?.parse()?,
+            "--height" => options.height = value()?.parse()?,
+            "--png" => options.png = Some(PathBuf::from(value()?)),
+            "--pbm" => options.pbm = Some(PathBuf::from(value()?)),
+            "--pgm" => options.pgm = Some(PathBuf::from(value()?)),
+            "--layers" => options.layers = Some(PathBuf::from(value()?)),
+            "--scale" => match value()?.parse()? {
+                0 => return Err(Box::from("--scale must be positive")),
+                scale => options.scale = scale
+            },
+            "--palette" => options.palette = Palette::parse(&value()?)?,
+            "--import" => options.import = Some(PathBuf::from(value()?)),
+            "--import-layers" => options.import_layers = value()?.parse()?,
+            "--checksum" => options.checksums.push(Checksum::parse(&value()?)?),
+            "--report" => options.report = true,
+            _ => return Err(Box::from(format!("Unknown argument {}", arg)))
+        }
     }
-    result
+    Ok(options)
 }
 
-fn part1(image: &Vec<i32>) -> i32 {
-    image
-        .chunks(W * H)
-        .map(|layer| {
-            let c = counter(layer.into_iter());
-            (*c.get(&0).unwrap_or(&0), *c.get(&1).unwrap_or(&0) * *c.get(&2).unwrap_or(&0))
-        })
-        .min()
-        .unwrap_or((-1, -1))
-        .1
+fn part1(image: &SpaceImage, checksum: &Checksum) -> AocResult<usize> {
+    Ok(checksum.compute(&stats::histograms(image))?.1)
 }
 
-fn part2(image: &Vec<i32>) {
-    let mut merged = vec![2; W * H];
-    for layer in image.chunks(W * H) {
-        for (i, &color) in layer.into_iter().enumerate() {
-            if merged[i] == 2 && color != 2 {
-                merged[i] = color;
-            }
+fn part2(image: &SpaceImage) -> String {
+    match ocr::recognize(&image.bitmap()) {
+        Ok(password) => password,
+        Err(e) => {
+            eprintln!("{}", e);
+            image.render()
         }
     }
-    for row in merged.chunks(W) {
-        let s: String = row
-            .into_iter()
-            .map(|i| match i {
-                0 => ' ',
-                1 => 'O',
-                _ => panic!("Illegal colour")
-            })
-            .collect();
-        println!("{}", s);
+}
+
+fn read_image(options: &Options) -> AocResult<SpaceImage> {
+    Ok(SpaceImage::decode(&fs::read_to_string(&options.input)?, options.width, options.height)?)
+}
+
+fn write_planes(image: &SpaceImage, options: &Options) -> AocResult<()> {
+    let merged = image.merged();
+    let plane = Plane::merged(image, &merged, options.scale);
+    if let Some(path) = &options.png {
+        convert::write_png(&plane, &options.palette, path)?;
+    }
+    if let Some(path) = &options.pbm {
+        fs::write(path, convert::pbm(&plane))?;
     }
+    if let Some(path) = &options.pgm {
+        fs::write(path, convert::pgm(&plane, &options.palette))?;
+    }
+    if let Some(dir) = &options.layers {
+        fs::create_dir_all(dir)?;
+        for index in 0..image.layers().len() {
+            let plane = Plane::layer(image, index, options.scale);
+            convert::write_png(&plane, &options.palette, &dir.join(format!("layer_{:03}.png", index)))?;
+        }
+    }
+    Ok(())
 }
 
+fn import(path: &Path, layers: usize) -> AocResult<String> {
+    let image = convert::import_pbm(&fs::read(path)?)?;
+    Ok(convert::layered(&image, layers)?.encode())
+}
 
 fn main() -> AocResult<()> {
-    let input = std::fs::read_to_string("input.txt")?
-        .trim()
-        .chars()
-        .map(|ch| ch as i32 - '0' as i32)
-        .collect();
-    println!("{}", part1(&input));
-    part2(&input);
+    let options = parse_options()?;
+    if let Some(path) = &options.import {
+        println!("{}", import(path, options.import_layers)?);
+        return Ok(());
+    }
+    let image = read_image(&options)?;
+    write_planes(&image, &options)?;
+    if options.report {
+        print!("{}", stats::report(&image, &options.checksums));
+        return Ok(());
+    }
+    let checksum = options.checksums.first().cloned().unwrap_or_default();
+    println!("{}", part1(&image, &checksum)?);
+    println!("{}", part2(&image));
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answers() -> AocResult<()> {
+        let options = Options::default();
+        let image = read_image(&options)?;
+        assert_eq!(part1(&image, &Checksum::default())?, 2064);
+        assert_eq!(part2(&image), "KAUZA");
+        Ok(())
+    }
+}
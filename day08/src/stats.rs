@@ -0,0 +1,208 @@
+use std::error::Error;
+use std::fmt;
+use crate::image::SpaceImage;
+
+type AocResult<T> = std::result::Result<T, Box<dyn Error>>;
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+/// Number of pixels of each digit in one layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Histogram([usize; 10]);
+
+impl Histogram {
+    pub fn of(pixels: &[u8]) -> Histogram {
+        let mut counts = [0; 10];
+        for &pixel in pixels {
+            counts[pixel as usize] += 1;
+        }
+        Histogram(counts)
+    }
+
+    pub fn count(&self, digit: u8) -> usize {
+        self.0[digit as usize]
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+}
+
+pub fn histograms(image: &SpaceImage) -> Vec<Histogram> {
+    image.layers().iter().map(|layer| Histogram::of(layer)).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extreme { Fewest, Most }
+
+/// Index of the layer with the fewest or most pixels of `digit`, the first one on ties.
+pub fn select_layer(histograms: &[Histogram], digit: u8, extreme: Extreme) -> Option<usize> {
+    let counts = histograms.iter().map(|histogram| histogram.count(digit)).enumerate();
+    match extreme {
+        Extreme::Fewest => counts.min_by_key(|&(_, count)| count),
+        Extreme::Most => counts.rev().max_by_key(|&(_, count)| count)
+    }.map(|(index, _)| index)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation { Sum, Product }
+
+/// Picks a layer by one digit and combines the counts of other digits in it, written as e.g.
+/// `min0:1*2` (the puzzle's checksum) or `max2:0+1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checksum {
+    pub extreme: Extreme,
+    pub digit: u8,
+    pub operation: Operation,
+    pub operands: Vec<u8>
+}
+
+impl Default for Checksum {
+    fn default() -> Checksum {
+        Checksum {extreme: Extreme::Fewest, digit: 0, operation: Operation::Product, operands: vec![1, 2]}
+    }
+}
+
+fn parse_digit(s: &str) -> AocResult<u8> {
+    match s.as_bytes() {
+        [digit @ b'0'..=b'9'] => Ok(digit - b'0'),
+        _ => err!("Expected a digit, got {:?}", s)
+    }
+}
+
+impl Checksum {
+    pub fn parse(spec: &str) -> AocResult<Checksum> {
+        let (selector, formula) = spec.split_once(':').ok_or(format!("Expected SELECTOR:FORMULA, got {:?}", spec))?;
+        let (extreme, digit) = if let Some(digit) = selector.strip_prefix("min") {
+            (Extreme::Fewest, digit)
+        } else if let Some(digit) = selector.strip_prefix("max") {
+            (Extreme::Most, digit)
+        } else {
+            return err!("Expected min or max, got {:?}", selector);
+        };
+        let operation = if formula.contains('+') { Operation::Sum } else { Operation::Product };
+        let separator = if operation == Operation::Sum { '+' } else { '*' };
+        if operation == Operation::Sum && formula.contains('*') {
+            return err!("Cannot mix + and * in {:?}", formula);
+        }
+        let operands = formula.split(separator).map(parse_digit).collect::<AocResult<_>>()?;
+        Ok(Checksum {extreme, digit: parse_digit(digit)?, operation, operands})
+    }
+
+    /// The selected layer and its checksum; fails for an image without layers, or when the
+    /// checksum does not fit in a `usize`.
+    pub fn compute(&self, histograms: &[Histogram]) -> AocResult<(usize, usize)> {
+        let index = select_layer(histograms, self.digit, self.extreme).ok_or("Image has no layers")?;
+        let mut counts = self.operands.iter().map(|&digit| histograms[index].count(digit));
+        let value = match self.operation {
+            Operation::Sum => counts.try_fold(0, usize::checked_add),
+            Operation::Product => counts.try_fold(1, usize::checked_mul)
+        }
+            .ok_or(format!("Checksum {} overflows", self))?;
+        Ok((index, value))
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let extreme = match self.extreme {
+            Extreme::Fewest => "min",
+            Extreme::Most => "max"
+        };
+        let separator = match self.operation {
+            Operation::Sum => "+",
+            Operation::Product => "*"
+        };
+        let operands: Vec<String> = self.operands.iter().map(|digit| digit.to_string()).collect();
+        write!(f, "{}{}:{}", extreme, self.digit, operands.join(separator))
+    }
+}
+
+/// Tables of per-layer digit counts, the extreme layers for every digit that occurs, and the
+/// given checksums.
+pub fn report(image: &SpaceImage, checksums: &[Checksum]) -> String {
+    let histograms = histograms(image);
+    let digits: Vec<u8> = (0..10).filter(|&digit| histograms.iter().any(|h| h.count(digit) > 0)).collect();
+    let mut result = format!("{} x {}, {} layers\n\n", image.width(), image.height(), histograms.len());
+
+    result.push_str(&format!("{:>5}", "layer"));
+    for digit in &digits {
+        result.push_str(&format!("{:>6}", digit));
+    }
+    result.push('\n');
+    for (index, histogram) in histograms.iter().enumerate() {
+        result.push_str(&format!("{:>5}", index));
+        for &digit in &digits {
+            result.push_str(&format!("{:>6}", histogram.count(digit)));
+        }
+        result.push('\n');
+    }
+
+    result.push_str(&format!("\n{:>5}{:>8}{:>8}\n", "digit", "fewest", "most"));
+    for &digit in &digits {
+        let fewest = select_layer(&histograms, digit, Extreme::Fewest).unwrap_or_default();
+        let most = select_layer(&histograms, digit, Extreme::Most).unwrap_or_default();
+        result.push_str(&format!("{:>5}{:>8}{:>8}\n", digit, fewest, most));
+    }
+
+    if !checksums.is_empty() {
+        result.push_str(&format!("\n{:>12}{:>7}{:>10}\n", "checksum", "layer", "value"));
+        for checksum in checksums {
+            match checksum.compute(&histograms) {
+                Ok((index, value)) => result.push_str(&format!("{:>12}{:>7}{:>10}\n", checksum.to_string(), index, value)),
+                Err(e) => result.push_str(&format!("{:>12}  {}\n", checksum.to_string(), e))
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() -> AocResult<()> {
+        let image = SpaceImage::decode("123456789012", 3, 2)?;
+        let counts = histograms(&image);
+        assert_eq!(counts[1].count(0), 1);
+        assert_eq!(counts[1].total(), 6);
+        assert_eq!(Checksum::default().compute(&counts)?, (0, 1));
+        assert_eq!(select_layer(&counts, 2, Extreme::Most), Some(0));
+        assert_eq!(select_layer(&counts, 9, Extreme::Fewest), Some(0));
+        assert_eq!(select_layer(&[], 0, Extreme::Fewest), None);
+
+        let image = SpaceImage::decode("0001110221120000", 2, 2)?;
+        let counts = histograms(&image);
+        assert_eq!(Checksum::parse("min0:1*2")?, Checksum::default());
+        assert_eq!(Checksum::parse("min0:1*2")?.compute(&counts)?, (2, 4));
+        assert_eq!(Checksum::parse("max0:1+2")?.compute(&counts)?, (3, 0));
+        assert_eq!(Checksum::parse("max1:0")?.compute(&counts)?, (1, 1));
+        assert!(Checksum::default().compute(&[]).is_err());
+
+        // 10^30 does not fit.
+        let image = SpaceImage::decode(&"1".repeat(10), 10, 1)?;
+        let overflow = Checksum::parse(&format!("min0:{}", ["1"; 30].join("*")))?;
+        assert!(overflow.compute(&histograms(&image)).is_err());
+        assert_eq!(Checksum::parse("max1:0+2")?.to_string(), "max1:0+2");
+        for spec in &["min0", "avg0:1", "min10:1", "min0:1*x", "min0:1+2*3"] {
+            assert!(Checksum::parse(spec).is_err(), "{}", spec);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_report() -> AocResult<()> {
+        let image = SpaceImage::decode("0222112222120000", 2, 2)?;
+        let report = report(&image, &[Checksum::default()]);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "2 x 2, 4 layers");
+        assert_eq!(lines[2], "layer     0     1     2");
+        assert_eq!(lines[3], "    0     1     0     3");
+        assert_eq!(lines[9], "    0       1       3");
+        assert_eq!(lines[14], "    min0:1*2      1         4");
+        Ok(())
+    }
+}
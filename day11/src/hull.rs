@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::mpsc::channel;
+use std::thread;
+use intcode::{Intcode, AocResult};
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction { Up, Right, Down, Left }
+
+impl Direction {
+    pub fn turn_left(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        self.turn_left().turn_left().turn_left()
+    }
+
+    // The y axis points up.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color { Black, White }
+
+impl Color {
+    fn from_code(code: i64) -> AocResult<Color> {
+        match code {
+            0 => Ok(Color::Black),
+            1 => Ok(Color::White),
+            c => err!("Illegal color: {}", c)
+        }
+    }
+
+    fn code(self) -> i64 {
+        match self {
+            Color::Black => 0,
+            Color::White => 1
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: (i32, i32),
+    pub max: (i32, i32)
+}
+
+impl Bounds {
+    fn around(point: (i32, i32)) -> Bounds {
+        Bounds {min: point, max: point}
+    }
+
+    fn extend(self, point: (i32, i32)) -> Bounds {
+        Bounds {
+            min: (self.min.0.min(point.0), self.min.1.min(point.1)),
+            max: (self.max.0.max(point.0), self.max.1.max(point.1))
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.0 - self.min.0 + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.1 - self.min.1 + 1) as usize
+    }
+}
+
+/// Panels the robot has painted; unpainted panels are black.
+#[derive(Clone, Default)]
+pub struct Hull {
+    panels: HashMap<(i32, i32), Color>
+}
+
+impl Hull {
+    pub fn new() -> Hull {
+        Hull {panels: HashMap::new()}
+    }
+
+    pub fn color(&self, position: (i32, i32)) -> Color {
+        *self.panels.get(&position).unwrap_or(&Color::Black)
+    }
+
+    pub fn paint(&mut self, position: (i32, i32), color: Color) {
+        self.panels.insert(position, color);
+    }
+
+    pub fn painted_count(&self) -> usize {
+        self.panels.len()
+    }
+
+    /// Smallest rectangle containing every painted panel.
+    pub fn bounds(&self) -> Option<Bounds> {
+        Self::bounds_of_points(self.panels.keys())
+    }
+
+    /// Smallest rectangle containing every panel of the given colour.
+    pub fn bounds_of(&self, color: Color) -> Option<Bounds> {
+        Self::bounds_of_points(self.panels.iter().filter(|(_, &c)| c == color).map(|(p, _)| p))
+    }
+
+    fn bounds_of_points<'a, I>(mut points: I) -> Option<Bounds>
+        where I: Iterator<Item = &'a (i32, i32)>
+    {
+        let first = Bounds::around(*points.next()?);
+        Some(points.fold(first, |bounds, &point| bounds.extend(point)))
+    }
+}
+
+/// One paint-turn-move cycle of the robot.
+#[derive(Clone, Copy, Debug)]
+pub struct Step {
+    pub position: (i32, i32),
+    pub color: Color,
+    pub direction: Direction
+}
+
+pub struct Robot {
+    pub position: (i32, i32),
+    pub direction: Direction,
+    pub history: Vec<Step>
+}
+
+impl Robot {
+    pub fn new() -> Robot {
+        Robot {position: (0, 0), direction: Direction::Up, history: Vec::new()}
+    }
+
+    /// Runs the painting program until it halts.
+    pub fn paint(&mut self, program: &[i64], hull: &mut Hull) -> AocResult<()> {
+        let (sender, robot_receiver) = channel();
+        let (robot_sender, receiver) = channel();
+        let mut intcode = Intcode::new(program.to_vec(), robot_receiver, robot_sender);
+        thread::spawn(move || intcode.exec().unwrap());
+
+        // Once the program halts its ends of the channels are dropped, failing the send or recv.
+        loop {
+            if sender.send(hull.color(self.position).code()).is_err() {
+                return Ok(());
+            }
+            let color = match receiver.recv() {
+                Ok(c) => Color::from_code(c)?,
+                Err(_) => return Ok(())
+            };
+            let direction = match receiver.recv() {
+                Ok(0) => self.direction.turn_left(),
+                Ok(1) => self.direction.turn_right(),
+                Ok(c) => return err!("Illegal rotation: {}", c),
+                Err(_) => return Ok(())
+            };
+            self.step(hull, color, direction);
+        }
+    }
+
+    pub fn step(&mut self, hull: &mut Hull, color: Color, direction: Direction) {
+        hull.paint(self.position, color);
+        self.history.push(Step {position: self.position, color, direction});
+        self.direction = direction;
+        let offset = direction.offset();
+        self.position = (self.position.0 + offset.0, self.position.1 + offset.1);
+    }
+}
+
+impl Default for Robot {
+    fn default() -> Robot {
+        Robot::new()
+    }
+}
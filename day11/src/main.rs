@@ -1,86 +1,105 @@
+use std::env;
 use std::fs;
-use std::sync::mpsc::channel;
+use std::path::Path;
 use std::thread;
-use std::error::Error;
-use std::collections::HashMap;
-use itertools::Itertools;
-use intcode::{Intcode, AocResult};
+use std::time::Duration;
+use intcode::AocResult;
 
-macro_rules! err {
-    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
-}
-
-fn paint(program: &Vec<i64>, white: &mut HashMap<(i32, i32), bool>) -> AocResult<()> {
-    let (sender, robot_receiver) = channel();
-    let (robot_sender, receiver) = channel();
-    let mut robot = Intcode::new(program.clone(), robot_receiver, robot_sender);
-    thread::spawn(move || robot.exec().unwrap());
+mod hull;
+mod render;
 
-    let directions = vec![(0, 1), (1, 0), (0, -1), (-1, 0)];
-    let mut dir_idx: usize = 0;
-    let mut pos = (0, 0);
+use hull::{Color, Hull, Robot};
 
-    loop {
-        let e = white.entry(pos.clone()).or_insert(false);
-        let msg = match &e {
-            true => 1,
-            false => 0
-        };
-        sender.send(msg)?;
-        *e = match receiver.recv() {
-            Ok(c) if c == 0 => false,
-            Ok(c) if c == 1 => true,
-            Ok(c) => return err!("Illegal color: {}", c),
-            Err(_) => return Ok(())
-        };
+struct Options {
+    pbm: Option<String>,
+    png: Option<String>,
+    frames: Option<String>,
+    animate: bool,
+    scale: usize
+}
 
-        dir_idx = match receiver.recv() {
-            Ok(c) if c == 0 => match dir_idx {
-                0 => 3,
-                v => v - 1
+fn parse_options() -> AocResult<Options> {
+    let mut options = Options {pbm: None, png: None, frames: None, animate: false, scale: 4};
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--pbm" => options.pbm = Some(value()?),
+            "--png" => options.png = Some(value()?),
+            "--frames" => options.frames = Some(value()?),
+            "--animate" => options.animate = true,
+            "--scale" => match value()?.parse()? {
+                0 => return Err(Box::from("--scale must be positive")),
+                scale => options.scale = scale
             },
-            Ok(c) if c == 1 => (dir_idx + 1) % 4,
-            Ok(c) => return err!("Illegal rotation: {}", c),
-            Err(_) => return Ok(())
-        };
-        let direction = directions[dir_idx];
-        pos.0 += direction.0;
-        pos.1 += direction.1;
+            _ => return Err(Box::from(format!("Unknown argument {}", arg)))
+        }
     }
+    Ok(options)
 }
 
-fn part1(program: &Vec<i64>) -> AocResult<()> {
-    let mut white = HashMap::new();
-    paint(&program, &mut white)?;
-    println!("{}", white.len());
-    Ok(())
+fn part1(program: &[i64]) -> AocResult<usize> {
+    let mut hull = Hull::new();
+    Robot::new().paint(program, &mut hull)?;
+    Ok(hull.painted_count())
 }
 
+fn part2(program: &[i64], options: &Options) -> AocResult<String> {
+    let mut start = Hull::new();
+    start.paint((0, 0), Color::White);
+    let mut hull = start.clone();
+    let mut robot = Robot::new();
+    robot.paint(program, &mut hull)?;
+    let bounds = hull.bounds_of(Color::White).ok_or("Nothing was painted white")?;
 
-fn part2(program: &Vec<i64>) -> AocResult<()> {
-    let mut white = HashMap::new();
-    white.insert((0, 0), true);
-    paint(&program, &mut white)?;
-    let (x_low, x_high) = white.keys().map(|(x, _)| x).minmax().into_option().unwrap();
-    let (y_low, y_high) = white.keys().map(|(_, y)| y).minmax().into_option().unwrap();
-    for y in (*y_low..=*y_high).rev() {
-        let mut s = String::new();
-        for x in *x_low..=*x_high {
-            let ch = match white.get(&(x, y)) {
-                Some(true) => '0',
-                _ => ' '
-            };
-            s.push(ch)
+    if let Some(path) = &options.pbm {
+        fs::write(path, render::pbm(&hull, bounds, options.scale))?;
+    }
+    if let Some(path) = &options.png {
+        render::write_png(&hull, bounds, options.scale, Path::new(path))?;
+    }
+    let every = std::cmp::max(1, robot.history.len() / 200);
+    if let Some(dir) = &options.frames {
+        let bounds = hull.bounds().unwrap_or(bounds);
+        render::write_png_frames(&start, &robot.history, bounds, options.scale, every, Path::new(dir))?;
+    }
+    if options.animate {
+        let bounds = hull.bounds().unwrap_or(bounds);
+        for frame in render::frames(&start, &robot.history, bounds).iter().step_by(every) {
+            println!("\x1b[2J\x1b[H{}", frame);
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+    match ocr::recognize(&render::bitmap(&hull, bounds)) {
+        Ok(registration) => Ok(registration),
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(render::ascii(&hull, bounds))
         }
-        println!("{}", s);
     }
-    Ok(())
 }
 
-fn main() -> AocResult<()> {
+fn read_program() -> AocResult<Vec<i64>> {
     let input = fs::read_to_string("input.txt")?;
-    let program = input.split(',').map(|s| s.parse::<i64>()).collect::<Result<Vec<_>, _>>()?;
-    part1(&program)?;
-    part2(&program)?;
+    Ok(input.trim().split(',').map(|s| s.parse::<i64>()).collect::<Result<Vec<_>, _>>()?)
+}
+
+fn main() -> AocResult<()> {
+    let options = parse_options()?;
+    let program = read_program()?;
+    println!("{}", part1(&program)?);
+    println!("{}", part2(&program, &options)?);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration() -> AocResult<()> {
+        let options = Options {pbm: None, png: None, frames: None, animate: false, scale: 1};
+        assert_eq!(part2(&read_program()?, &options)?, "UZAEKBLP");
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use intcode::AocResult;
+use crate::hull::{Bounds, Color, Direction, Hull, Step};
+
+const WHITE: u8 = 255;
+const BLACK: u8 = 0;
+const ROBOT: u8 = 128;
+
+// Rows from the top (largest y) down, so that the picture is not upside down.
+fn rows(bounds: Bounds) -> impl Iterator<Item = impl Iterator<Item = (i32, i32)>> {
+    (bounds.min.1..=bounds.max.1)
+        .rev()
+        .map(move |y| (bounds.min.0..=bounds.max.0).map(move |x| (x, y)))
+}
+
+fn grayscale(hull: &Hull, bounds: Bounds, robot: Option<(i32, i32)>) -> Vec<Vec<u8>> {
+    rows(bounds)
+        .map(|row| {
+            row.map(|position| match hull.color(position) {
+                _ if Some(position) == robot => ROBOT,
+                Color::White => WHITE,
+                Color::Black => BLACK
+            }).collect()
+        })
+        .collect()
+}
+
+fn scaled(image: Vec<Vec<u8>>, scale: usize) -> Vec<Vec<u8>> {
+    image
+        .into_iter()
+        .flat_map(|row| {
+            let row: Vec<u8> = row.into_iter().flat_map(|pixel| vec![pixel; scale]).collect();
+            vec![row; scale]
+        })
+        .collect()
+}
+
+pub fn bitmap(hull: &Hull, bounds: Bounds) -> Vec<Vec<bool>> {
+    rows(bounds).map(|row| row.map(|position| hull.color(position) == Color::White).collect()).collect()
+}
+
+pub fn ascii(hull: &Hull, bounds: Bounds) -> String {
+    rows(bounds)
+        .map(|row| {
+            row.map(|position| match hull.color(position) {
+                Color::White => '#',
+                Color::Black => ' '
+            }).collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Plain (P1) portable bitmap, where 1 is ink, i.e. a black panel, so that it looks the same as
+/// the PNG.
+pub fn pbm(hull: &Hull, bounds: Bounds, scale: usize) -> String {
+    let image = scaled(grayscale(hull, bounds, None), scale);
+    let mut result = format!("P1\n{} {}\n", bounds.width() * scale, bounds.height() * scale);
+    for row in image {
+        let line: Vec<&str> = row.iter().map(|&pixel| if pixel == BLACK { "1" } else { "0" }).collect();
+        result.push_str(&line.join(" "));
+        result.push('\n');
+    }
+    result
+}
+
+fn write_grayscale_png(image: Vec<Vec<u8>>, path: &Path) -> AocResult<()> {
+    let height = image.len() as u32;
+    let width = image.first().map_or(0, |row| row.len()) as u32;
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.concat())?;
+    Ok(())
+}
+
+pub fn write_png(hull: &Hull, bounds: Bounds, scale: usize, path: &Path) -> AocResult<()> {
+    write_grayscale_png(scaled(grayscale(hull, bounds, None), scale), path)
+}
+
+/// Hull states after each step of `history`, starting from `start`, with the robot drawn on top.
+fn replay<'a>(start: &Hull, history: &'a [Step]) -> impl Iterator<Item = (Hull, Step)> + 'a {
+    let mut hull = start.clone();
+    history.iter().map(move |&step| {
+        hull.paint(step.position, step.color);
+        (hull.clone(), step)
+    })
+}
+
+fn robot_position(step: &Step) -> (i32, i32) {
+    let offset = step.direction.offset();
+    (step.position.0 + offset.0, step.position.1 + offset.1)
+}
+
+/// ASCII animation of the robot painting the hull, one frame per step.
+pub fn frames(start: &Hull, history: &[Step], bounds: Bounds) -> Vec<String> {
+    replay(start, history)
+        .map(|(hull, step)| {
+            let robot = robot_position(&step);
+            let marker = match step.direction {
+                Direction::Up => '^',
+                Direction::Right => '>',
+                Direction::Down => 'v',
+                Direction::Left => '<'
+            };
+            rows(bounds)
+                .map(|row| {
+                    row.map(|position| match hull.color(position) {
+                        _ if position == robot => marker,
+                        Color::White => '#',
+                        Color::Black => ' '
+                    }).collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect()
+}
+
+/// Writes every `every`-th step as `frame_NNNNN.png` into `dir`, returning the number of frames.
+pub fn write_png_frames(start: &Hull, history: &[Step], bounds: Bounds, scale: usize, every: usize, dir: &Path)
+    -> AocResult<usize>
+{
+    fs::create_dir_all(dir)?;
+    let mut count = 0;
+    for (i, (hull, step)) in replay(start, history).enumerate() {
+        if i % every != 0 && i + 1 != history.len() {
+            continue;
+        }
+        let image = grayscale(&hull, bounds, Some(robot_position(&step)));
+        write_grayscale_png(scaled(image, scale), &dir.join(format!("frame_{:05}.png", count)))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hull::Robot;
+
+    #[test]
+    fn test_render() {
+        let mut hull = Hull::new();
+        let mut robot = Robot::new();
+        robot.step(&mut hull, Color::White, Direction::Left);
+        robot.step(&mut hull, Color::Black, Direction::Down);
+        robot.step(&mut hull, Color::White, Direction::Right);
+        robot.step(&mut hull, Color::White, Direction::Up);
+
+        let bounds = hull.bounds().unwrap();
+        assert_eq!(bounds, Bounds {min: (-1, -1), max: (0, 0)});
+        assert_eq!(hull.bounds_of(Color::White), Some(Bounds {min: (-1, -1), max: (0, 0)}));
+        assert_eq!(hull.painted_count(), 4);
+        assert_eq!(ascii(&hull, bounds), " #\n##");
+        assert_eq!(pbm(&hull, bounds, 1), "P1\n2 2\n1 0\n0 0\n");
+        assert_eq!(frames(&Hull::new(), &robot.history, bounds), vec!["<#\n  ", " #\nv ", " #\n#>", " ^\n##"]);
+    }
+}
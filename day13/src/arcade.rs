@@ -0,0 +1,338 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::thread;
+use intcode::{Intcode, AocResult};
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile { Empty, Wall, Block, Paddle, Ball }
+
+impl Tile {
+    fn from_code(code: i64) -> AocResult<Tile> {
+        match code {
+            0 => Ok(Tile::Empty),
+            1 => Ok(Tile::Wall),
+            2 => Ok(Tile::Block),
+            3 => Ok(Tile::Paddle),
+            4 => Ok(Tile::Ball),
+            c => err!("Illegal tile: {}", c)
+        }
+    }
+
+    pub fn glyph(self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '#',
+            Tile::Block => '=',
+            Tile::Paddle => '-',
+            Tile::Ball => 'o'
+        }
+    }
+
+    fn rgb(self) -> [u8; 3] {
+        match self {
+            Tile::Empty => [0, 0, 0],
+            Tile::Wall => [128, 128, 128],
+            Tile::Block => [200, 80, 40],
+            Tile::Paddle => [240, 240, 240],
+            Tile::Ball => [250, 220, 0]
+        }
+    }
+}
+
+/// Decides the joystick position (-1, 0 or 1) before every tick of the game,
+/// or ends the game early by returning `None`.
+pub trait Controller {
+    fn joystick(&mut self, arcade: &Arcade) -> AocResult<Option<i64>>;
+}
+
+/// Moves the paddle towards the ball's current column.
+pub struct Tracking;
+
+impl Controller for Tracking {
+    fn joystick(&mut self, arcade: &Arcade) -> AocResult<Option<i64>> {
+        match (arcade.ball(), arcade.paddle()) {
+            (Some(ball), Some(paddle)) => Ok(Some((ball.0 - paddle.0).signum())),
+            _ => Ok(Some(0))
+        }
+    }
+}
+
+/// Moves the paddle to where the ball will come down, simulating its bounces off walls and
+/// blocks on a copy of the board.
+pub struct Predictive;
+
+impl Predictive {
+    fn landing_column(arcade: &Arcade, ball: (i64, i64), paddle: (i64, i64)) -> i64 {
+        let (mut x, mut y) = ball;
+        let (mut dx, mut dy) = arcade.ball_velocity();
+        if dx == 0 || dy == 0 {
+            return x;
+        }
+        let mut broken = HashSet::new();
+        let mut solid = |x: i64, y: i64| match arcade.tile(x, y) {
+            Tile::Wall => true,
+            // A block only stops the ball once.
+            Tile::Block => broken.insert((x, y)),
+            _ => false
+        };
+        // Bounded, in case the ball gets stuck bouncing between blocks in the simulation.
+        for _ in 0..10000 {
+            if dy > 0 && y >= paddle.1 - 1 {
+                break;
+            }
+            let (bounce_x, bounce_y) = (solid(x + dx, y), solid(x, y + dy));
+            if bounce_x {
+                dx = -dx;
+            }
+            if bounce_y {
+                dy = -dy;
+            }
+            if !bounce_x && !bounce_y && solid(x + dx, y + dy) {
+                dx = -dx;
+                dy = -dy;
+            }
+            x += dx;
+            y += dy;
+        }
+        x
+    }
+}
+
+impl Controller for Predictive {
+    fn joystick(&mut self, arcade: &Arcade) -> AocResult<Option<i64>> {
+        match (arcade.ball(), arcade.paddle()) {
+            (Some(ball), Some(paddle)) => Ok(Some((Self::landing_column(arcade, ball, paddle) - paddle.0).signum())),
+            _ => Ok(Some(0))
+        }
+    }
+}
+
+/// Reads one line per tick: "a" moves left, "d" moves right, anything else stays.
+/// The game ends when the input does. Showing the board is up to the caller, e.g. from `on_frame`.
+pub struct Keyboard {
+    input: Box<dyn BufRead>
+}
+
+impl Keyboard {
+    pub fn new(input: Box<dyn BufRead>) -> Keyboard {
+        Keyboard {input}
+    }
+}
+
+impl Controller for Keyboard {
+    fn joystick(&mut self, _: &Arcade) -> AocResult<Option<i64>> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match line.trim() {
+            "a" => Ok(Some(-1)),
+            "d" => Ok(Some(1)),
+            _ => Ok(Some(0))
+        }
+    }
+}
+
+/// Game state rebuilt from the cabinet's output.
+#[derive(Clone, Default)]
+pub struct Arcade {
+    tiles: HashMap<(i64, i64), Tile>,
+    width: i64,
+    height: i64,
+    score: i64,
+    ball: Option<(i64, i64)>,
+    ball_velocity: (i64, i64),
+    paddle: Option<(i64, i64)>,
+    blocks: usize,
+    frame: usize
+}
+
+impl Arcade {
+    pub fn new() -> Arcade {
+        Arcade::default()
+    }
+
+    /// Plays the game until the program halts, asking `controller` for input on every tick
+    /// and calling `on_frame` after it.
+    pub fn run<F>(program: Vec<i64>, controller: &mut dyn Controller, mut on_frame: F) -> AocResult<Arcade>
+        where F: FnMut(&Arcade) -> AocResult<()>
+    {
+        let (sender, cabinet_receiver) = channel();
+        let (cabinet_sender, receiver) = channel();
+        let mut cabinet = Intcode::new(program, cabinet_receiver, cabinet_sender);
+        let handle = thread::spawn(move || cabinet.exec().map_err(|e| e.to_string()));
+
+        let mut arcade = Arcade::new();
+        // The cabinet reads the joystick once per tick, right after drawing the ball.
+        while let Ok(x) = receiver.recv() {
+            let y = receiver.recv()?;
+            let value = receiver.recv()?;
+            if arcade.apply(x, y, value)? == Some(Tile::Ball) {
+                on_frame(&arcade)?;
+                match controller.joystick(&arcade)? {
+                    // A halted cabinet no longer listens, but its remaining output still has to be read.
+                    Some(joystick) => { let _ = sender.send(joystick); },
+                    // Hanging up makes the cabinet fail on its next read, which is not an error here.
+                    None => return Ok(arcade)
+                }
+            }
+        }
+        match handle.join() {
+            Ok(Ok(())) => Ok(arcade),
+            Ok(Err(e)) => err!("Cabinet failed: {}", e),
+            Err(_) => err!("Cabinet panicked")
+        }
+    }
+
+    /// Updates the state with one output triple, returning the tile that was drawn, if any.
+    pub fn apply(&mut self, x: i64, y: i64, value: i64) -> AocResult<Option<Tile>> {
+        if x == -1 && y == 0 {
+            self.score = value;
+            return Ok(None);
+        }
+        let tile = Tile::from_code(value)?;
+        let previous = self.tiles.insert((x, y), tile).unwrap_or(Tile::Empty);
+        self.width = self.width.max(x + 1);
+        self.height = self.height.max(y + 1);
+        if previous == Tile::Block {
+            self.blocks -= 1;
+        }
+        match tile {
+            Tile::Block => self.blocks += 1,
+            Tile::Paddle => self.paddle = Some((x, y)),
+            Tile::Ball => {
+                if let Some(ball) = self.ball {
+                    self.ball_velocity = (x - ball.0, y - ball.1);
+                }
+                self.ball = Some((x, y));
+                self.frame += 1;
+            },
+            _ => {}
+        }
+        Ok(Some(tile))
+    }
+
+    pub fn tile(&self, x: i64, y: i64) -> Tile {
+        *self.tiles.get(&(x, y)).unwrap_or(&Tile::Empty)
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    pub fn ball(&self) -> Option<(i64, i64)> {
+        self.ball
+    }
+
+    pub fn ball_velocity(&self) -> (i64, i64) {
+        self.ball_velocity
+    }
+
+    pub fn paddle(&self) -> Option<(i64, i64)> {
+        self.paddle
+    }
+
+    pub fn blocks_remaining(&self) -> usize {
+        self.blocks
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn render(&self) -> String {
+        let mut result = format!("Score: {}  Blocks: {}  Frame: {}\n", self.score, self.blocks, self.frame);
+        for y in 0..self.height() {
+            let row: String = (0..self.width()).map(|x| self.tile(x, y).glyph()).collect();
+            result.push_str(&row);
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Writes the board as a binary PPM image with every tile drawn as a `scale` x `scale` square.
+    pub fn write_ppm(&self, path: &Path, scale: usize) -> AocResult<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        write!(file, "P6\n{} {}\n255\n", self.width() as usize * scale, self.height() as usize * scale)?;
+        for y in 0..self.height() {
+            let row: Vec<u8> = (0..self.width())
+                .flat_map(|x| self.tile(x, y).rgb().repeat(scale))
+                .collect();
+            for _ in 0..scale {
+                file.write_all(&row)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> AocResult<Arcade> {
+        let mut arcade = Arcade::new();
+        for x in 0..10 {
+            arcade.apply(x, 0, 1)?;
+        }
+        for y in 1..8 {
+            arcade.apply(0, y, 1)?;
+            arcade.apply(9, y, 1)?;
+        }
+        arcade.apply(3, 2, 2)?;
+        arcade.apply(4, 2, 2)?;
+        arcade.apply(2, 7, 3)?;
+        arcade.apply(-1, 0, 17)?;
+        Ok(arcade)
+    }
+
+    #[test]
+    fn test_state() -> AocResult<()> {
+        let mut arcade = board()?;
+        assert_eq!(arcade.apply(5, 3, 4)?, Some(Tile::Ball));
+        assert_eq!(arcade.apply(6, 4, 4)?, Some(Tile::Ball));
+        arcade.apply(4, 2, 0)?;
+        assert_eq!((arcade.width(), arcade.height()), (10, 8));
+        assert_eq!(arcade.score(), 17);
+        assert_eq!(arcade.blocks_remaining(), 1);
+        assert_eq!(arcade.ball(), Some((6, 4)));
+        assert_eq!(arcade.ball_velocity(), (1, 1));
+        assert_eq!(arcade.paddle(), Some((2, 7)));
+        assert_eq!(arcade.frame(), 2);
+        assert_eq!(arcade.render().lines().nth(3), Some("#  =     #"));
+        assert!(arcade.apply(1, 1, 5).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_controllers() -> AocResult<()> {
+        let mut arcade = board()?;
+        arcade.apply(6, 3, 4)?;
+        arcade.apply(7, 4, 4)?;
+        // The ball bounces off the right wall at x = 8 and comes down at x = 7.
+        assert_eq!(Predictive::landing_column(&arcade, (7, 4), (2, 7)), 7);
+        assert_eq!(Predictive.joystick(&arcade)?, Some(1));
+        assert_eq!(Tracking.joystick(&arcade)?, Some(1));
+        arcade.apply(2, 7, 0)?;
+        arcade.apply(8, 7, 3)?;
+        assert_eq!(Predictive.joystick(&arcade)?, Some(-1));
+        assert_eq!(Tracking.joystick(&arcade)?, Some(-1));
+        Ok(())
+    }
+}
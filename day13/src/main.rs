@@ -1,69 +1,80 @@
+use std::env;
 use std::fs;
-use std::thread;
-use std::sync::mpsc::{channel, Receiver};
-use intcode::{Intcode, AocResult};
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+use intcode::AocResult;
 
+mod arcade;
+mod play;
 
-fn part1(program: &Vec<i64>) -> i32 {
-    let (_, robot_receiver) = channel();
-    let (robot_sender, receiver) = channel();
-    let mut robot = Intcode::new(program.clone(), robot_receiver, robot_sender);
-    thread::spawn(move || robot.exec().unwrap()).join().unwrap();
+use arcade::{Arcade, Controller, Keyboard, Predictive, Tracking};
 
-    let received: Vec<_> = receiver.iter().collect();
-    let mut blocks_count = 0;
-    for obj in received.chunks(3) {
-        if obj[2] == 2 {
-            blocks_count += 1;
-        }
-    }
-    blocks_count
+struct Options {
+    controller: String,
+    render: bool,
+    frames: Option<PathBuf>,
+    play: bool
 }
 
-fn get_initial_paddle(receiver: &Receiver<i64>) -> AocResult<i64> {
-    loop {
-        let x = receiver.recv()?;
-        receiver.recv()?;
-        let tile = receiver.recv()?;
-        if tile == 3 {
-            return Ok(x);
+fn parse_options() -> AocResult<Options> {
+    let mut options = Options {controller: "tracking".to_owned(), render: false, frames: None, play: false};
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--controller" => options.controller = value()?,
+            "--render" => options.render = true,
+            "--play" => options.play = true,
+            "--frames" => options.frames = Some(PathBuf::from(value()?)),
+            _ => return Err(Box::from(format!("Unknown argument {}", arg)))
         }
     }
+    Ok(options)
 }
 
-fn part2(mut program: Vec<i64>) -> AocResult<i64> {
-    let (sender, robot_receiver) = channel();
-    let (robot_sender, receiver) = channel();
-
-    let mut result = 0;
-    program[0] = 2;
-    sender.send(0)?;
+fn part1(program: &[i64]) -> AocResult<usize> {
+    let arcade = Arcade::run(program.to_vec(), &mut Tracking, |_| Ok(()))?;
+    Ok(arcade.blocks_remaining())
+}
 
-    let mut robot = Intcode::new(program, robot_receiver, robot_sender);
-    thread::spawn(move || robot.exec().unwrap());
+fn part2(program: &[i64], options: &Options) -> AocResult<i64> {
+    let mut controller: Box<dyn Controller> = match options.controller.as_str() {
+        "tracking" => Box::new(Tracking),
+        "predictive" => Box::new(Predictive),
+        "keyboard" => Box::new(Keyboard::new(Box::new(BufReader::new(io::stdin())))),
+        other => return Err(Box::from(format!("Unknown controller {}", other)))
+    };
+    if let Some(dir) = &options.frames {
+        fs::create_dir_all(dir)?;
+    }
 
-    let mut paddle = get_initial_paddle(&receiver)?;
-    loop {
-        let x = match receiver.recv() {
-            Ok(x) => x,
-            Err(_) => return Ok(result)
-        };
-        receiver.recv()?;
-        let tile = receiver.recv()?;
-        if x == -1 && tile > result {
-            result = tile;
-        } else if tile == 4 {
-            let msg = (x - paddle).signum();
-            paddle += msg;
-            sender.send(msg)?;
+    // Keyboard players need to see the board before every move.
+    let render = options.render || options.controller == "keyboard";
+    let mut program = program.to_vec();
+    program[0] = 2;
+    let arcade = Arcade::run(program, controller.as_mut(), |arcade| {
+        if render {
+            println!("{}", arcade.render());
         }
+        if let Some(dir) = &options.frames {
+            arcade.write_ppm(&dir.join(format!("frame_{:05}.ppm", arcade.frame())), 8)?;
+        }
+        Ok(())
+    })?;
+    if arcade.blocks_remaining() > 0 {
+        return Err(Box::from(format!("Game over with {} blocks left", arcade.blocks_remaining())));
     }
+    Ok(arcade.score())
 }
 
 fn main() -> AocResult<()> {
+    let options = parse_options()?;
     let input = fs::read_to_string("input.txt")?;
-    let program = input.split(',').map(|s| s.parse::<i64>()).collect::<Result<Vec<_>, _>>()?;
-    println!("{}", part1(&program));
-    println!("{}", part2(program)?);
+    let program = input.trim().split(',').map(|s| s.parse::<i64>()).collect::<Result<Vec<_>, _>>()?;
+    if options.play {
+        return play::play(&program);
+    }
+    println!("{}", part1(&program)?);
+    println!("{}", part2(&program, &options)?);
     Ok(())
 }
@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use intcode::AocResult;
+use crate::arcade::{Arcade, Controller};
+
+const TICK: Duration = Duration::from_millis(120);
+const HELP: &str = "<-/-> move  p pause  s save  l load  q quit";
+
+enum Request { Quit, Load }
+
+/// The game is deterministic, so a save state is just the joystick inputs that led to it.
+struct Human<'a> {
+    stdout: &'a mut Stdout,
+    replay: VecDeque<i64>,
+    log: Vec<i64>,
+    saved: &'a mut Option<Vec<i64>>,
+    request: Option<Request>
+}
+
+impl<'a> Human<'a> {
+    fn status(&mut self, message: &str) -> AocResult<()> {
+        queue!(self.stdout, MoveTo(0, 0), Clear(ClearType::CurrentLine), Print(message))?;
+        self.stdout.flush()?;
+        Ok(())
+    }
+
+    fn wait_for_unpause(&mut self) -> AocResult<bool> {
+        self.status("Paused, press p to continue")?;
+        loop {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('p') | KeyCode::Char(' ') if key.kind == KeyEventKind::Press => return Ok(true),
+                    KeyCode::Char('q') if key.kind == KeyEventKind::Press => return Ok(false),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Controller for Human<'a> {
+    fn joystick(&mut self, arcade: &Arcade) -> AocResult<Option<i64>> {
+        // Fast-forward through a loaded state without waiting for the player.
+        if let Some(joystick) = self.replay.pop_front() {
+            self.log.push(joystick);
+            return Ok(Some(joystick));
+        }
+        draw(self.stdout, arcade, HELP)?;
+
+        let deadline = Instant::now() + TICK;
+        let mut joystick = 0;
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            if !event::poll(timeout)? {
+                break;
+            }
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue
+            };
+            match key.code {
+                KeyCode::Left | KeyCode::Char('a') => joystick = -1,
+                KeyCode::Right | KeyCode::Char('d') => joystick = 1,
+                KeyCode::Char('p') | KeyCode::Char(' ') => {
+                    let resumed = self.wait_for_unpause()?;
+                    if !resumed {
+                        self.request = Some(Request::Quit);
+                        return Ok(None);
+                    }
+                },
+                KeyCode::Char('s') => {
+                    *self.saved = Some(self.log.clone());
+                    self.status("Saved")?;
+                },
+                KeyCode::Char('l') if self.saved.is_some() => {
+                    self.request = Some(Request::Load);
+                    return Ok(None);
+                },
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    self.request = Some(Request::Quit);
+                    return Ok(None);
+                },
+                _ => {}
+            }
+        }
+        self.log.push(joystick);
+        Ok(Some(joystick))
+    }
+}
+
+/// Plays back recorded inputs at normal speed; any key stops the replay.
+struct Replay<'a> {
+    stdout: &'a mut Stdout,
+    inputs: std::vec::IntoIter<i64>
+}
+
+impl<'a> Controller for Replay<'a> {
+    fn joystick(&mut self, arcade: &Arcade) -> AocResult<Option<i64>> {
+        draw(self.stdout, arcade, "Replay, press any key to stop")?;
+        if event::poll(TICK)? {
+            if let Event::Key(_) = event::read()? {
+                return Ok(None);
+            }
+        }
+        Ok(self.inputs.next())
+    }
+}
+
+fn draw(stdout: &mut Stdout, arcade: &Arcade, message: &str) -> AocResult<()> {
+    queue!(stdout, MoveTo(0, 0), Clear(ClearType::CurrentLine), Print(message))?;
+    for (i, line) in arcade.render().lines().enumerate() {
+        queue!(stdout, MoveTo(0, i as u16 + 1), Print(line))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+fn read_choice() -> AocResult<char> {
+    loop {
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char(ch) if key.kind == KeyEventKind::Press => return Ok(ch),
+                KeyCode::Esc => return Ok('q'),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn game_loop(stdout: &mut Stdout, program: &[i64]) -> AocResult<()> {
+    let mut program = program.to_vec();
+    program[0] = 2;
+    let mut saved = None;
+    let mut replay = VecDeque::new();
+    loop {
+        let mut human = Human {stdout, replay, log: vec![], saved: &mut saved, request: None};
+        let arcade = Arcade::run(program.clone(), &mut human, |_| Ok(()))?;
+        let (log, request) = (human.log, human.request);
+        replay = VecDeque::new();
+        match request {
+            Some(Request::Quit) => return Ok(()),
+            Some(Request::Load) => {
+                replay = saved.clone().unwrap_or_default().into();
+                continue;
+            },
+            None => {}
+        }
+
+        loop {
+            let result = match arcade.blocks_remaining() {
+                0 => "You win!",
+                _ => "Game over."
+            };
+            let message = format!("{} Score {}. n new game, r replay, q quit", result, arcade.score());
+            draw(stdout, &arcade, &message)?;
+            match read_choice()? {
+                'n' => break,
+                'r' => {
+                    let mut controller = Replay {stdout, inputs: log.clone().into_iter()};
+                    Arcade::run(program.clone(), &mut controller, |_| Ok(()))?;
+                },
+                'q' => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Restores the terminal when dropped, so that it is usable again even after a panic.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter(stdout: &mut Stdout) -> AocResult<RawTerminal> {
+        terminal::enable_raw_mode()?;
+        let guard = RawTerminal;
+        execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+        Ok(guard)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Runs the game interactively in the terminal until the player quits.
+pub fn play(program: &[i64]) -> AocResult<()> {
+    let mut stdout = io::stdout();
+    let _terminal = RawTerminal::enter(&mut stdout)?;
+    game_loop(&mut stdout, program)
+}
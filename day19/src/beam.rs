@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::mpsc::channel;
+use intcode::{Intcode, AocResult};
+
+type Probe = Box<dyn FnMut(i64, i64) -> AocResult<bool>>;
+
+// How far down, in multiples of the square size, to look for a square before giving up.
+const SQUARE_SEARCH_FACTOR: i64 = 1000;
+
+/// Tractor beam edges, discovered row by row. Both edges of the beam are assumed to only move
+/// right as `y` grows, so every row takes just a few probes starting from the previous one.
+pub struct Beam {
+    probe: Probe,
+    cache: HashMap<(i64, i64), bool>,
+    rows: Vec<Option<(i64, i64)>>,
+    invocations: usize
+}
+
+impl Beam {
+    pub fn new(probe: Probe) -> Beam {
+        Beam {probe, cache: HashMap::new(), rows: Vec::new(), invocations: 0}
+    }
+
+    /// Probes by running the drone program once per point.
+    pub fn from_program(program: Vec<i64>) -> Beam {
+        Beam::new(Box::new(move |x, y| {
+            let (sender, drone_receiver) = channel();
+            let (drone_sender, receiver) = channel();
+            let mut intcode = Intcode::new(program.clone(), drone_receiver, drone_sender);
+            sender.send(x)?;
+            sender.send(y)?;
+            intcode.exec()?;
+            match receiver.recv()? {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(Box::<dyn Error>::from("Incorrect output"))
+            }
+        }))
+    }
+
+    pub fn invocations(&self) -> usize {
+        self.invocations
+    }
+
+    pub fn has_beam(&mut self, x: i64, y: i64) -> AocResult<bool> {
+        if let Some(&result) = self.cache.get(&(x, y)) {
+            return Ok(result);
+        }
+        let result = (self.probe)(x, y)?;
+        self.invocations += 1;
+        self.cache.insert((x, y), result);
+        Ok(result)
+    }
+
+    /// First and last column of the beam in row `y`, or `None` if the row misses it.
+    pub fn row(&mut self, y: i64) -> AocResult<Option<(i64, i64)>> {
+        if y < 0 {
+            return Err(Box::from(format!("Invalid row {}", y)));
+        }
+        while self.rows.len() as i64 <= y {
+            let next = self.rows.len() as i64;
+            let edges = self.scan_row(next)?;
+            self.rows.push(edges);
+        }
+        Ok(self.rows[y as usize])
+    }
+
+    // The last row with a beam, ignoring the single point at the origin.
+    fn previous(&self) -> Option<(i64, (i64, i64))> {
+        self.rows.iter().enumerate().skip(1).rev().find_map(|(y, row)| row.map(|edges| (y as i64, edges)))
+    }
+
+    fn scan_row(&mut self, y: i64) -> AocResult<Option<(i64, i64)>> {
+        // The beam widens linearly from the origin, which bounds where the row can start.
+        let (from, limit) = match self.previous() {
+            Some((py, (start, end))) => (start, (end + 1) * (y + 1) / (py + 1) + 2),
+            None => (0, 10 * (y + 1))
+        };
+        let mut start = from;
+        while !self.has_beam(start, y)? {
+            if start >= limit {
+                return Ok(None);
+            }
+            start += 1;
+        }
+        let mut end = match self.previous() {
+            Some((_, (_, end))) if end > start && self.has_beam(end, y)? => end,
+            _ => start
+        };
+        while self.has_beam(end + 1, y)? {
+            end += 1;
+        }
+        Ok(Some((start, end)))
+    }
+
+    /// Number of points affected by the beam in the `width` x `height` area at the origin.
+    pub fn area(&mut self, width: i64, height: i64) -> AocResult<i64> {
+        let mut result = 0;
+        for y in 0..height {
+            if let Some((start, end)) = self.row(y)? {
+                result += (end.min(width - 1) - start + 1).max(0);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Top left corner of the closest `size` x `size` square that fits entirely in the beam.
+    pub fn first_square(&mut self, size: i64) -> AocResult<(i64, i64)> {
+        if size < 1 {
+            return Err(Box::from(format!("Invalid square size {}", size)));
+        }
+        let mut bottom = size - 1;
+        while bottom < SQUARE_SEARCH_FACTOR * size {
+            let top = bottom - size + 1;
+            if let (Some((top_start, top_end)), Some((left, bottom_end))) = (self.row(top)?, self.row(bottom)?) {
+                if top_start <= left && top_end >= left + size - 1 && bottom_end >= left + size - 1 {
+                    return Ok((left, top));
+                }
+            }
+            bottom += 1;
+        }
+        Err(Box::from(format!("No {0} x {0} square in the first {1} rows", size, bottom)))
+    }
+}
+
+/// A beam between the lines x = y / 2 and x = 3y / 4, with an empty row near the origin.
+#[cfg(test)]
+pub fn synthetic(x: i64, y: i64) -> AocResult<bool> {
+    Ok((x, y) == (0, 0) || (y > 1 && 2 * x >= y && 4 * x <= 3 * y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_square(size: i64) -> AocResult<(i64, i64)> {
+        for top in 0.. {
+            for left in 0..=top {
+                let fits = synthetic(left, top)? && synthetic(left + size - 1, top)?
+                    && synthetic(left, top + size - 1)? && synthetic(left + size - 1, top + size - 1)?;
+                if fits {
+                    return Ok((left, top));
+                }
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn test_rows() -> AocResult<()> {
+        let mut beam = Beam::new(Box::new(synthetic));
+        assert_eq!(beam.row(0)?, Some((0, 0)));
+        assert_eq!(beam.row(1)?, None);
+        assert_eq!(beam.row(8)?, Some((4, 6)));
+        for y in 2..200 {
+            let expected = ((y + 1) / 2, 3 * y / 4);
+            assert_eq!(beam.row(y)?, Some(expected));
+        }
+        assert!(beam.invocations() < 200 * 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_square() -> AocResult<()> {
+        for &size in &[1, 2, 5, 10, 17] {
+            let mut beam = Beam::new(Box::new(synthetic));
+            assert_eq!(beam.first_square(size)?, brute_force_square(size)?, "size {}", size);
+        }
+        let mut beam = Beam::new(Box::new(synthetic));
+        assert!(beam.first_square(0).is_err());
+        assert!(beam.row(-1).is_err());
+        // A beam one point wide never fits a square.
+        let mut line = Beam::new(Box::new(|x, y| Ok(x == y)));
+        assert!(line.first_square(2).is_err());
+        Ok(())
+    }
+}
@@ -1,80 +1,88 @@
+use std::env;
 use std::fs;
-use std::sync::mpsc::channel;
-use std::error::Error;
-use std::collections::HashMap;
-use intcode::{Intcode, AocResult};
+use std::path::PathBuf;
+use intcode::AocResult;
 
-enum HasBeam {
-    Yes,
-    No
-}
+mod beam;
+mod render;
 
-fn has_beam(program: &Vec<i64>, x: i64, y: i64) -> AocResult<HasBeam> {
-    let (sender, robot_receiver) = channel();
-    let (robot_sender, receiver) = channel();
-    let mut intcode = Intcode::new(program.clone(), robot_receiver, robot_sender);
-    sender.send(x)?;
-    sender.send(y)?;
-    intcode.exec()?;
-    match receiver.recv()? {
-        0 => Ok(HasBeam::No),
-        1 => Ok(HasBeam::Yes),
-        _ => Err(Box::<dyn Error>::from("Incorrect output"))
-    }
+use beam::Beam;
+use render::Region;
+
+const SHIP: i64 = 100;
+
+struct Options {
+    ascii: bool,
+    pgm: Option<PathBuf>,
+    csv: Option<PathBuf>,
+    extent: Option<i64>,
+    scale: usize,
+    verbose: bool
 }
 
-fn part1(program: &Vec<i64>) -> AocResult<i64> {
-    let mut result = 0;
-    for x in 0..50 {
-        for y in 0..50 {
-            if let HasBeam::Yes = has_beam(program, x, y)? {
-                result += 1
-            }
+fn parse_options() -> AocResult<Options> {
+    let mut options = Options {ascii: false, pgm: None, csv: None, extent: None, scale: 1, verbose: false};
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--ascii" => options.ascii = true,
+            "--pgm" => options.pgm = Some(PathBuf::from(value()?)),
+            "--csv" => options.csv = Some(PathBuf::from(value()?)),
+            "--extent" => match value()?.parse()? {
+                extent if extent > 0 => options.extent = Some(extent),
+                _ => return Err(Box::from("--extent must be positive"))
+            },
+            "--scale" => match value()?.parse()? {
+                0 => return Err(Box::from("--scale must be positive")),
+                scale => options.scale = scale
+            },
+            "--verbose" => options.verbose = true,
+            _ => return Err(Box::from(format!("Unknown argument {}", arg)))
         }
     }
-    Ok(result)
+    Ok(options)
 }
 
-fn part2(program: &Vec<i64>) -> AocResult<i64> {
-    const SIZE: i64 = 100;
-    let mut last_y_with_beam = HashMap::new();
-    let mut x = SIZE;
-    loop {
-        // boundaries were obtained with NN-based image recognition
-        let mut lo = x / 2;
-        let mut hi = x;
-        while lo < hi {
-            let mid = lo + (hi - lo + 1) / 2;
-            match has_beam(program, x, mid)? {
-                HasBeam::Yes => lo = mid,
-                HasBeam::No => hi = mid - 1,
-            }
-        }
-        last_y_with_beam.insert(x, lo);
+fn part1(beam: &mut Beam) -> AocResult<i64> {
+    beam.area(50, 50)
+}
 
-        if let Some(right_y) = last_y_with_beam.get(&(x - SIZE + 1)) {
-            lo = 0;
-            hi = x / 2;
-            while lo < hi {
-                let mid = (lo + hi) / 2;
-                match has_beam(program, x, mid)? {
-                    HasBeam::No => lo = mid + 1,
-                    HasBeam::Yes => hi = mid,
-                }
-            }
-            let left_y = lo;
-            if right_y - left_y >= SIZE - 1 {
-                return Ok((x - SIZE + 1) * 10000 + lo)
-            }
-        }
-        x += 1;
+fn part2(beam: &mut Beam) -> AocResult<i64> {
+    let (x, y) = beam.first_square(SHIP)?;
+    Ok(x * 10000 + y)
+}
+
+fn visualize(beam: &mut Beam, options: &Options) -> AocResult<()> {
+    let square = beam.first_square(SHIP)?;
+    // By default, show everything up to a little past the ship.
+    let extent = options.extent.unwrap_or(square.0.max(square.1) + SHIP + 10);
+    let region = Region {width: extent, height: extent, square: Some((square, SHIP))};
+    if options.ascii {
+        println!("{}", render::ascii(beam, &region)?);
+    }
+    if let Some(path) = &options.pgm {
+        render::write_pgm(beam, &region, path, options.scale)?;
     }
+    if let Some(path) = &options.csv {
+        fs::write(path, render::csv(beam, extent)?)?;
+    }
+    eprintln!("{} of {} points in the beam", beam.area(extent, extent)?, extent * extent);
+    Ok(())
 }
 
 fn main() -> AocResult<()> {
+    let options = parse_options()?;
     let input = fs::read_to_string("input.txt")?;
-    let program = input.split(',').map(|s| s.parse::<i64>()).collect::<Result<Vec<_>, _>>()?;
-    println!("{:?}", part1(&program)?);
-    println!("{:?}", part2(&program)?);
+    let program = input.trim().split(',').map(|s| s.parse::<i64>()).collect::<Result<Vec<_>, _>>()?;
+    let mut beam = Beam::from_program(program);
+    println!("{:?}", part1(&mut beam)?);
+    println!("{:?}", part2(&mut beam)?);
+    if options.ascii || options.pgm.is_some() || options.csv.is_some() {
+        visualize(&mut beam, &options)?;
+    }
+    if options.verbose {
+        eprintln!("{} drone deployments", beam.invocations());
+    }
     Ok(())
-}
\ No newline at end of file
+}
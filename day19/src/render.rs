@@ -0,0 +1,94 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use intcode::AocResult;
+use crate::beam::Beam;
+
+const EMPTY: u8 = 0;
+const BEAM: u8 = 160;
+const SHIP: u8 = 255;
+
+/// The scanned area from (0, 0) to (`width` - 1, `height` - 1), with the ship square of side
+/// `size` at `square` drawn on top.
+pub struct Region {
+    pub width: i64,
+    pub height: i64,
+    pub square: Option<((i64, i64), i64)>
+}
+
+impl Region {
+    fn in_square(&self, x: i64, y: i64) -> bool {
+        match self.square {
+            Some(((left, top), size)) => (left..left + size).contains(&x) && (top..top + size).contains(&y),
+            None => false
+        }
+    }
+
+    fn shades(&self, beam: &mut Beam) -> AocResult<Vec<Vec<u8>>> {
+        let mut result = vec![];
+        for y in 0..self.height {
+            let row = beam.row(y)?;
+            let line = (0..self.width).map(|x| match row {
+                _ if self.in_square(x, y) => SHIP,
+                Some((start, end)) if (start..=end).contains(&x) => BEAM,
+                _ => EMPTY
+            });
+            result.push(line.collect());
+        }
+        Ok(result)
+    }
+}
+
+pub fn ascii(beam: &mut Beam, region: &Region) -> AocResult<String> {
+    let lines: Vec<String> = region.shades(beam)?
+        .into_iter()
+        .map(|row| row.into_iter().map(|shade| match shade {
+            SHIP => 'O',
+            BEAM => '#',
+            _ => '.'
+        }).collect())
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// Writes the region as a binary PGM image with every point drawn as a `scale` x `scale` square.
+pub fn write_pgm(beam: &mut Beam, region: &Region, path: &Path, scale: usize) -> AocResult<()> {
+    let shades = region.shades(beam)?;
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "P5\n{} {}\n255\n", region.width as usize * scale, region.height as usize * scale)?;
+    for row in shades {
+        let row: Vec<u8> = row.into_iter().flat_map(|shade| vec![shade; scale]).collect();
+        for _ in 0..scale {
+            file.write_all(&row)?;
+        }
+    }
+    Ok(())
+}
+
+/// First and last beam column of every row, left empty for rows the beam misses.
+pub fn csv(beam: &mut Beam, rows: i64) -> AocResult<String> {
+    let mut result = String::from("y,start,end,width\n");
+    for y in 0..rows {
+        match beam.row(y)? {
+            Some((start, end)) => result.push_str(&format!("{},{},{},{}\n", y, start, end, end - start + 1)),
+            None => result.push_str(&format!("{},,,0\n", y))
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beam::synthetic;
+
+    #[test]
+    fn test_render() -> AocResult<()> {
+        let mut beam = Beam::new(Box::new(synthetic));
+        let region = Region {width: 6, height: 8, square: Some(((4, 6), 1))};
+        assert_eq!(ascii(&mut beam, &region)?, "#.....\n......\n.#....\n..#...\n..##..\n...#..\n...#O.\n....##");
+        assert_eq!(beam.area(6, 8)?, 10);
+        assert_eq!(&csv(&mut beam, 4)?, "y,start,end,width\n0,0,0,1\n1,,,0\n2,1,1,1\n3,2,2,1\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::Write;
+use intcode::AocResult;
+use crate::network::Packet;
+
+/// UDP port used for both ends of every captured packet.
+const PORT: u16 = 2323;
+/// LINKTYPE_RAW: every record starts directly with an IPv4 header.
+const LINKTYPE_RAW: u32 = 101;
+
+/// One JSON object per line, in the order the packets were sent:
+/// `{"tick":3,"source":0,"destination":12,"x":1,"y":-5}`.
+pub fn write_jsonl<W: Write>(packets: &[Packet], out: &mut W) -> AocResult<()> {
+    for packet in packets {
+        writeln!(out, "{{\"tick\":{},\"source\":{},\"destination\":{},\"x\":{},\"y\":{}}}",
+                 packet.tick, packet.source, packet.destination, packet.x, packet.y)?;
+    }
+    Ok(())
+}
+
+/// NIC `n` lives at 10.0.0.n; larger addresses spill into the middle octets.
+fn ip_address(address: i64) -> [u8; 4] {
+    let address = address as u32 & 0x00ff_ffff;
+    [10, (address >> 16) as u8, (address >> 8) as u8, address as u8]
+}
+
+fn checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header.chunks(2).map(|word| u32::from(u16::from_be_bytes([word[0], word[1]]))).sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// IPv4 + UDP datagram carrying X and Y as big-endian 64-bit integers.
+fn datagram(packet: &Packet, id: u16) -> Vec<u8> {
+    let payload: Vec<u8> = [packet.x.to_be_bytes(), packet.y.to_be_bytes()].concat();
+    let udp_length = 8 + payload.len() as u16;
+    let total_length = 20 + udp_length;
+
+    let mut result = vec![0x45, 0];
+    result.extend_from_slice(&total_length.to_be_bytes());
+    result.extend_from_slice(&id.to_be_bytes());
+    result.extend_from_slice(&[0x40, 0, 64, 17, 0, 0]);
+    result.extend_from_slice(&ip_address(packet.source));
+    result.extend_from_slice(&ip_address(packet.destination));
+    let header_checksum = checksum(&result);
+    result[10..12].copy_from_slice(&header_checksum.to_be_bytes());
+
+    result.extend_from_slice(&PORT.to_be_bytes());
+    result.extend_from_slice(&PORT.to_be_bytes());
+    result.extend_from_slice(&udp_length.to_be_bytes());
+    // A zero UDP checksum means "not computed", which IPv4 allows.
+    result.extend_from_slice(&[0, 0]);
+    result.extend_from_slice(&payload);
+    result
+}
+
+/// Classic libpcap file. Timestamps are synthetic: the tick in seconds and the position of the
+/// packet within its tick in microseconds, so tools keep the original order.
+pub fn write_pcap<W: Write>(packets: &[Packet], out: &mut W) -> AocResult<()> {
+    out.write_all(&0xa1b2_c3d4u32.to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&4u16.to_le_bytes())?;
+    out.write_all(&0i32.to_le_bytes())?;
+    out.write_all(&0u32.to_le_bytes())?;
+    out.write_all(&65535u32.to_le_bytes())?;
+    out.write_all(&LINKTYPE_RAW.to_le_bytes())?;
+
+    let mut previous_tick = None;
+    let mut position = 0u32;
+    for (id, packet) in packets.iter().enumerate() {
+        position = if previous_tick == Some(packet.tick) { position + 1 } else { 0 };
+        previous_tick = Some(packet.tick);
+        let data = datagram(packet, id as u16);
+        out.write_all(&(packet.tick as u32).to_le_bytes())?;
+        out.write_all(&position.to_le_bytes())?;
+        out.write_all(&(data.len() as u32).to_le_bytes())?;
+        out.write_all(&(data.len() as u32).to_le_bytes())?;
+        out.write_all(&data)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packets() -> Vec<Packet> {
+        vec![
+            Packet {source: 3, destination: 255, x: 1, y: -5, tick: 7},
+            Packet {source: 255, destination: 0, x: 1, y: -5, tick: 7}
+        ]
+    }
+
+    #[test]
+    fn test_jsonl() -> AocResult<()> {
+        let mut out = vec![];
+        write_jsonl(&packets(), &mut out)?;
+        assert_eq!(String::from_utf8(out)?.lines().next(), Some("{\"tick\":7,\"source\":3,\"destination\":255,\"x\":1,\"y\":-5}"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcap() -> AocResult<()> {
+        let mut out = vec![];
+        write_pcap(&packets(), &mut out)?;
+        assert_eq!(out.len(), 24 + 2 * (16 + 44));
+        assert_eq!(&out[20..24], &[101, 0, 0, 0]);
+
+        let (record, data) = out[24..].split_at(16);
+        assert_eq!(&record[..8], &[7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&out[24 + 60 + 4..24 + 60 + 8], &[1, 0, 0, 0]);
+        assert_eq!(checksum(&data[..20]), 0);
+        assert_eq!(&data[12..20], &[10, 0, 0, 3, 10, 0, 0, 255]);
+        assert_eq!(&data[36..44], &(-5i64).to_be_bytes());
+        Ok(())
+    }
+}
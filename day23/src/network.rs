@@ -0,0 +1,181 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use intcode::{Intcode, AocResult};
+
+/// Instructions a NIC may run per tick before yielding to the next one, in case it never
+/// reads its input.
+const SLICE: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub source: i64,
+    pub destination: i64,
+    pub x: i64,
+    pub y: i64,
+    pub tick: usize
+}
+
+/// Where the NAT listens, whom it wakes up, and after how many idle ticks.
+/// A disabled NAT still records packets sent to its address but never delivers them.
+#[derive(Clone, Copy, Debug)]
+pub struct NatConfig {
+    pub enabled: bool,
+    pub address: i64,
+    pub target: i64,
+    pub idle_ticks: usize
+}
+
+impl Default for NatConfig {
+    fn default() -> NatConfig {
+        NatConfig {enabled: true, address: 255, target: 0, idle_ticks: 1}
+    }
+}
+
+struct Nic {
+    intcode: Intcode,
+    input: Sender<i64>,
+    output: Receiver<i64>,
+    queue: VecDeque<i64>,
+    pending: Vec<i64>
+}
+
+impl Nic {
+    fn boot(program: Vec<i64>, address: i64) -> Nic {
+        let (input, nic_receiver) = channel();
+        let (nic_sender, output) = channel();
+        let intcode = Intcode::new(program, nic_receiver, nic_sender);
+        Nic {intcode, input, output, queue: VecDeque::from(vec![address]), pending: vec![]}
+    }
+
+    /// Runs until the NIC finds its queue empty, returning whether it did anything else:
+    /// received data or sent some.
+    fn run_slice(&mut self) -> AocResult<bool> {
+        let mut busy = false;
+        for _ in 0..SLICE {
+            if !self.intcode.is_running() {
+                break;
+            }
+            let mut starved = false;
+            if self.intcode.peek(self.intcode.pc()) % 100 == 3 {
+                match self.queue.pop_front() {
+                    Some(value) => { busy = true; self.input.send(value)?; },
+                    None => { starved = true; self.input.send(-1)?; }
+                }
+            }
+            self.intcode.step()?;
+            while let Ok(value) = self.output.try_recv() {
+                busy = true;
+                self.pending.push(value);
+            }
+            if starved {
+                return Ok(busy);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// NICs run one after another in a fixed order, and packets sent during a tick are delivered
+/// at its end, so every run of the same program produces the same log.
+pub struct Network {
+    nics: Vec<Nic>,
+    nat: NatConfig,
+    nat_packet: Option<(i64, i64)>,
+    idle: usize,
+    tick: usize,
+    pub log: Vec<Packet>
+}
+
+impl Network {
+    pub fn new(program: &[i64], size: usize, nat: NatConfig) -> Network {
+        let nics = (0..size).map(|address| Nic::boot(program.to_vec(), address as i64)).collect();
+        Network {nics, nat, nat_packet: None, idle: 0, tick: 0, log: vec![]}
+    }
+
+    /// Runs every NIC for one slice and returns the packets sent during it, NAT included.
+    pub fn step(&mut self) -> AocResult<Vec<Packet>> {
+        let mut packets = vec![];
+        let mut idle = true;
+        for (source, nic) in self.nics.iter_mut().enumerate() {
+            if nic.run_slice()? {
+                idle = false;
+            }
+            for chunk in nic.pending.chunks_exact(3) {
+                packets.push(Packet {source: source as i64, destination: chunk[0], x: chunk[1], y: chunk[2], tick: self.tick});
+            }
+            let sent = nic.pending.len() / 3 * 3;
+            nic.pending.drain(..sent);
+        }
+
+        self.idle = if idle { self.idle + 1 } else { 0 };
+        if self.nat.enabled && self.idle >= self.nat.idle_ticks {
+            if let Some((x, y)) = self.nat_packet {
+                packets.push(Packet {source: self.nat.address, destination: self.nat.target, x, y, tick: self.tick});
+                self.idle = 0;
+            }
+        }
+
+        for packet in &packets {
+            if packet.destination == self.nat.address {
+                self.nat_packet = Some((packet.x, packet.y));
+            } else if let Some(nic) = self.nics.get_mut(packet.destination as usize) {
+                nic.queue.push_back(packet.x);
+                nic.queue.push_back(packet.y);
+            }
+        }
+        self.log.extend_from_slice(&packets);
+        self.tick += 1;
+        Ok(packets)
+    }
+
+    /// Steps until `stop` accepts a packet, which is then returned.
+    pub fn run_until<F>(&mut self, max_ticks: usize, mut stop: F) -> AocResult<Packet>
+        where F: FnMut(&Packet) -> bool
+    {
+        for _ in 0..max_ticks {
+            if let Some(packet) = self.step()?.into_iter().find(|packet| stop(packet)) {
+                return Ok(packet);
+            }
+        }
+        Err(Box::from(format!("No result after {} ticks", max_ticks)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn program() -> AocResult<Vec<i64>> {
+        let input = fs::read_to_string("input.txt")?;
+        Ok(input.trim().split(',').map(|s| s.parse::<i64>()).collect::<Result<Vec<_>, _>>()?)
+    }
+
+    #[test]
+    fn test_deterministic() -> AocResult<()> {
+        let program = program()?;
+        let mut logs = vec![];
+        for _ in 0..2 {
+            let mut network = Network::new(&program, 50, NatConfig::default());
+            for _ in 0..100 {
+                network.step()?;
+            }
+            logs.push(network.log);
+        }
+        assert!(!logs[0].is_empty());
+        assert_eq!(logs[0], logs[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_disabled_nat() -> AocResult<()> {
+        let nat = NatConfig {enabled: false, ..NatConfig::default()};
+        let mut network = Network::new(&program()?, 50, nat);
+        for _ in 0..200 {
+            network.step()?;
+        }
+        assert!(network.log.iter().any(|packet| packet.destination == 255));
+        assert!(network.log.iter().all(|packet| packet.source != 255));
+        Ok(())
+    }
+}
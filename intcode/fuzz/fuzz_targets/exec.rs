@@ -0,0 +1,32 @@
+#![no_main]
+use std::sync::mpsc::channel;
+use libfuzzer_sys::fuzz_target;
+use intcode::Intcode;
+
+const BUDGET: usize = 10000;
+
+// Runs arbitrary words as a program; every outcome except a panic is acceptable.
+fuzz_target!(|data: &[u8]| {
+    let words: Vec<i64> = data
+        .chunks_exact(8)
+        .map(|chunk| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(chunk);
+            i64::from_le_bytes(bytes)
+        })
+        .collect();
+    let (inputs, program) = words.split_at(std::cmp::min(4, words.len()));
+
+    let (sender, receiver) = channel();
+    let (output, _outputs) = channel();
+    for &value in inputs {
+        sender.send(value).unwrap();
+    }
+    drop(sender);
+    let mut intcode = Intcode::new(program.to_vec(), receiver, output);
+    for _ in 0..BUDGET {
+        if !intcode.is_running() || intcode.step().is_err() {
+            break;
+        }
+    }
+});
@@ -0,0 +1,142 @@
+// Examples from the puzzle statements of days 2, 5 and 9, checked against every way of driving
+// `Intcode`: plain `exec`, single-stepping with the undo log, and a run that is rewound to the
+// start and replayed.
+
+use super::*;
+use std::sync::mpsc::channel;
+
+struct Run {
+    outputs: Vec<i64>,
+    memory: Vec<i64>
+}
+
+type Backend = fn(&[i64], &[i64]) -> AocResult<Run>;
+
+const BACKENDS: [(&str, Backend); 3] = [("exec", exec), ("step", step), ("rewind", rewind)];
+
+fn start(program: &[i64], inputs: &[i64]) -> AocResult<(Intcode, Receiver<i64>)> {
+    let (sender, receiver) = channel();
+    let (output, outputs) = channel();
+    for &value in inputs {
+        sender.send(value)?;
+    }
+    Ok((Intcode::new(program.to_vec(), receiver, output), outputs))
+}
+
+fn finish(intcode: Intcode, outputs: Receiver<i64>, len: usize) -> Run {
+    Run {
+        outputs: outputs.try_iter().collect(),
+        memory: (0..len).map(|address| intcode.peek(address)).collect()
+    }
+}
+
+fn exec(program: &[i64], inputs: &[i64]) -> AocResult<Run> {
+    let (mut intcode, outputs) = start(program, inputs)?;
+    intcode.exec()?;
+    Ok(finish(intcode, outputs, program.len()))
+}
+
+fn step(program: &[i64], inputs: &[i64]) -> AocResult<Run> {
+    let (mut intcode, outputs) = start(program, inputs)?;
+    intcode.enable_history();
+    while intcode.is_running() {
+        intcode.step()?;
+    }
+    Ok(finish(intcode, outputs, program.len()))
+}
+
+fn rewind(program: &[i64], inputs: &[i64]) -> AocResult<Run> {
+    let (mut intcode, outputs) = start(program, inputs)?;
+    intcode.enable_history();
+    intcode.exec()?;
+    while intcode.step_back() {}
+    if (0..program.len()).any(|address| intcode.peek(address) != program[address]) {
+        return err!("Memory was not restored by rewinding");
+    }
+    outputs.try_iter().for_each(drop);
+    intcode.exec()?;
+    Ok(finish(intcode, outputs, program.len()))
+}
+
+fn check_outputs(program: &[i64], inputs: &[i64], expected: &[i64]) -> AocResult<()> {
+    for (name, backend) in BACKENDS.iter() {
+        assert_eq!(backend(program, inputs)?.outputs, expected, "backend {}, inputs {:?}", name, inputs);
+    }
+    Ok(())
+}
+
+fn check_memory(program: &[i64], expected: &[i64]) -> AocResult<()> {
+    for (name, backend) in BACKENDS.iter() {
+        assert_eq!(backend(program, &[])?.memory, expected, "backend {}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_day2_arithmetic() -> AocResult<()> {
+    check_memory(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50], &[3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50])?;
+    check_memory(&[1, 0, 0, 0, 99], &[2, 0, 0, 0, 99])?;
+    check_memory(&[2, 3, 0, 3, 99], &[2, 3, 0, 6, 99])?;
+    check_memory(&[2, 4, 4, 5, 99, 0], &[2, 4, 4, 5, 99, 9801])?;
+    check_memory(&[1, 1, 1, 4, 99, 5, 6, 0, 99], &[30, 1, 1, 4, 2, 5, 6, 0, 99])?;
+    Ok(())
+}
+
+#[test]
+fn test_day5_modes() -> AocResult<()> {
+    check_memory(&[1002, 4, 3, 4, 33], &[1002, 4, 3, 4, 99])?;
+    check_memory(&[1101, 100, -1, 4, 0], &[1101, 100, -1, 4, 99])?;
+    check_outputs(&[3, 0, 4, 0, 99], &[42], &[42])?;
+    Ok(())
+}
+
+#[test]
+fn test_day5_comparisons() -> AocResult<()> {
+    let equal_position = [3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+    let less_position = [3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+    let equal_immediate = [3, 3, 1108, -1, 8, 3, 4, 3, 99];
+    let less_immediate = [3, 3, 1107, -1, 8, 3, 4, 3, 99];
+    for &input in &[-8, 0, 7, 8, 9] {
+        let (equal, less) = ((input == 8) as i64, (input < 8) as i64);
+        check_outputs(&equal_position, &[input], &[equal])?;
+        check_outputs(&less_position, &[input], &[less])?;
+        check_outputs(&equal_immediate, &[input], &[equal])?;
+        check_outputs(&less_immediate, &[input], &[less])?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_day5_jumps() -> AocResult<()> {
+    let position = [3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+    let immediate = [3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+    let around_eight = [
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31,
+        1106, 0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104,
+        999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105, 1, 46, 98, 99
+    ];
+    for &input in &[-1, 0, 1, 7, 8, 9] {
+        check_outputs(&position, &[input], &[(input != 0) as i64])?;
+        check_outputs(&immediate, &[input], &[(input != 0) as i64])?;
+        check_outputs(&around_eight, &[input], &[999 + (input - 8).signum() + 1])?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_day9_relative() -> AocResult<()> {
+    let quine = [109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+    check_outputs(&quine, &[], &quine)?;
+    // Relative writes land in the heap beyond the end of the program.
+    check_outputs(&[109, 50, 21101, 3, 4, 0, 204, 0, 99], &[], &[7])?;
+    check_outputs(&[109, 7, 22201, -7, -6, -5, 99], &[], &[])?;
+    check_memory(&[109, 7, 22201, -7, -6, -5, 99], &[109, 7, 116, -7, -6, -5, 99])?;
+    Ok(())
+}
+
+#[test]
+fn test_day9_large_numbers() -> AocResult<()> {
+    check_outputs(&[1102, 34915192, 34915192, 7, 4, 7, 99, 0], &[], &[1219070632396864])?;
+    check_outputs(&[104, 1125899906842624, 99], &[], &[1125899906842624])?;
+    Ok(())
+}
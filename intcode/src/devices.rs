@@ -0,0 +1,208 @@
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use crate::AocResult;
+
+/// A peripheral mapped onto a range of `Intcode` memory. Reads and writes of addresses inside
+/// the range are forwarded to the device with the offset from the start of the range.
+pub trait Device {
+    fn size(&self) -> usize;
+    fn read(&mut self, offset: usize) -> i64;
+    fn write(&mut self, offset: usize, value: i64);
+    /// Called once after every successfully executed instruction; an error is a fault of the
+    /// program.
+    fn tick(&mut self) -> AocResult<()> {
+        Ok(())
+    }
+}
+
+// Lets the caller keep a handle to a device after attaching it, e.g. to inspect a framebuffer.
+impl<D: Device> Device for Arc<Mutex<D>> {
+    fn size(&self) -> usize {
+        self.lock().unwrap().size()
+    }
+
+    fn read(&mut self, offset: usize) -> i64 {
+        self.lock().unwrap().read(offset)
+    }
+
+    fn write(&mut self, offset: usize, value: i64) {
+        self.lock().unwrap().write(offset, value)
+    }
+
+    fn tick(&mut self) -> AocResult<()> {
+        self.lock().unwrap().tick()
+    }
+}
+
+/// Row-major grid of pixels, one memory cell per pixel.
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<i64>
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> AocResult<Framebuffer> {
+        match width.checked_mul(height) {
+            Some(size) if size > 0 => Ok(Framebuffer {width, height, pixels: vec![0; size]}),
+            _ => Err(Box::<dyn Error>::from(format!("Invalid framebuffer size {} x {}", width, height)))
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> i64 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Draws the buffer with `palette[pixel]`, falling back to '?' for unknown colours.
+    pub fn render(&self, palette: &[char]) -> String {
+        self.pixels
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|&pixel| match pixel {
+                        p if p >= 0 && (p as usize) < palette.len() => palette[p as usize],
+                        _ => '?'
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Device for Framebuffer {
+    fn size(&self) -> usize {
+        self.pixels.len()
+    }
+
+    fn read(&mut self, offset: usize) -> i64 {
+        self.pixels[offset]
+    }
+
+    fn write(&mut self, offset: usize, value: i64) {
+        self.pixels[offset] = value;
+    }
+}
+
+/// Single cell holding the number of instructions executed since it was last written.
+#[derive(Default)]
+pub struct Clock {
+    ticks: i64
+}
+
+impl Clock {
+    pub fn new() -> Clock {
+        Clock {ticks: 0}
+    }
+}
+
+impl Device for Clock {
+    fn size(&self) -> usize {
+        1
+    }
+
+    fn read(&mut self, _offset: usize) -> i64 {
+        self.ticks
+    }
+
+    fn write(&mut self, _offset: usize, value: i64) {
+        self.ticks = value;
+    }
+
+    fn tick(&mut self) -> AocResult<()> {
+        match self.ticks.checked_add(1) {
+            Some(ticks) => self.ticks = ticks,
+            None => return Err(Box::from("Clock overflow"))
+        }
+        Ok(())
+    }
+}
+
+/// Single cell yielding a fresh non-negative pseudo-random number on every read.
+/// Writing to it reseeds the generator, so runs are reproducible.
+pub struct Random {
+    state: u64
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        let mut random = Random {state: 0};
+        random.write(0, seed as i64);
+        random
+    }
+}
+
+impl Device for Random {
+    fn size(&self) -> usize {
+        1
+    }
+
+    // xorshift64*
+    fn read(&mut self, _offset: usize) -> i64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 1) as i64
+    }
+
+    fn write(&mut self, _offset: usize, value: i64) {
+        // Zero is a fixed point of xorshift.
+        self.state = match value as u64 {
+            0 => 0x9e37_79b9_7f4a_7c15,
+            seed => seed
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Intcode, AocResult};
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_devices() -> AocResult<()> {
+        let framebuffer = Arc::new(Mutex::new(Framebuffer::new(3, 2)?));
+        let (_, receiver) = channel();
+        let (sender, outputs) = channel();
+        // Paints pixel 4, copies the clock to pixel 0, outputs two random numbers.
+        let program = vec![1101, 0, 2, 104, 1001, 1000, 0, 100, 4, 2000, 4, 2000, 99];
+        let mut intcode = Intcode::new(program, receiver, sender);
+        intcode.attach(100, Box::new(framebuffer.clone()))?;
+        intcode.attach(1000, Box::new(Clock::new()))?;
+        intcode.attach(2000, Box::new(Random::new(7)))?;
+        assert!(intcode.attach(102, Box::new(Clock::new())).is_err());
+        assert!(intcode.attach(usize::MAX - 1, Box::new(Framebuffer::new(3, 2)?)).is_err());
+        intcode.exec()?;
+
+        assert_eq!(framebuffer.lock().unwrap().render(&[' ', '#', '@']), "#  \n @ ");
+        let numbers: Vec<i64> = outputs.try_iter().collect();
+        let mut random = Random::new(7);
+        assert_eq!(numbers, vec![random.read(0), random.read(0)]);
+        assert_ne!(numbers[0], numbers[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_device_faults() -> AocResult<()> {
+        assert!(Framebuffer::new(0, 2).is_err());
+        assert!(Framebuffer::new(3, 0).is_err());
+
+        // An instruction that faults does not advance the clock.
+        let clock = Arc::new(Mutex::new(Clock::new()));
+        let (_, receiver) = channel();
+        let (sender, _outputs) = channel();
+        let mut intcode = Intcode::new(vec![1101, i64::MAX, 1, 50, 99], receiver, sender);
+        intcode.attach(1000, Box::new(clock.clone()))?;
+        assert!(intcode.step().is_err());
+        assert_eq!(clock.lock().unwrap().read(0), 0);
+
+        // Setting the clock to its maximum makes the next tick overflow.
+        let (_, receiver) = channel();
+        let (sender, _outputs) = channel();
+        let mut intcode = Intcode::new(vec![1101, i64::MAX, 0, 1000, 99], receiver, sender);
+        intcode.attach(1000, Box::new(Clock::new()))?;
+        assert!(intcode.exec().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+// Differential and fuzz tests: random programs are run on `Intcode` and on a deliberately naive
+// reference interpreter, and the observable state of both is compared.
+
+use super::*;
+use std::collections::BTreeMap;
+use std::sync::mpsc::channel;
+use proptest::prelude::*;
+
+const BUDGET: usize = 1000;
+
+#[derive(Debug, PartialEq)]
+enum Outcome { Halted, Faulted, OutOfBudget }
+
+#[derive(Debug, PartialEq)]
+struct State {
+    outcome: Outcome,
+    pc: usize,
+    outputs: Vec<i64>,
+    /// Every cell of the program plus every cell written past it.
+    memory: BTreeMap<i64, i64>,
+    cells: usize
+}
+
+struct Reference {
+    /// Sparse, so that any address a program computes can be used; missing cells are 0.
+    memory: HashMap<i64, i64>,
+    len: usize,
+    pc: usize,
+    base: i64
+}
+
+impl Reference {
+    fn read(&self, address: i64) -> Option<i64> {
+        if address < 0 {
+            return None;
+        }
+        Some(*self.memory.get(&address).unwrap_or(&0))
+    }
+
+    fn param(&mut self, n: usize) -> Option<i64> {
+        let word = self.read(self.pc as i64 + n as i64)?;
+        match self.read(self.pc as i64)? / [100, 1000, 10000][n - 1] % 10 {
+            0 => self.read(word),
+            1 => Some(word),
+            2 => self.read(word.checked_add(self.base)?),
+            _ => None
+        }
+    }
+
+    fn write(&mut self, n: usize, value: i64) -> Option<()> {
+        let word = self.read(self.pc as i64 + n as i64)?;
+        let address = match self.read(self.pc as i64)? / [100, 1000, 10000][n - 1] % 10 {
+            0 => word,
+            2 => word.checked_add(self.base)?,
+            _ => return None
+        };
+        self.read(address)?;
+        self.memory.insert(address, value);
+        Some(())
+    }
+
+    // Executes one instruction, returning `Some(false)` on halt and `None` on a fault.
+    fn step(&mut self, inputs: &mut Vec<i64>, outputs: &mut Vec<i64>) -> Option<bool> {
+        let opcode = self.read(self.pc as i64)?;
+        match opcode % 100 {
+            1 => {
+                let value = self.param(1)?.checked_add(self.param(2)?)?;
+                self.write(3, value)?;
+                self.pc += 4;
+            },
+            2 => {
+                let value = self.param(1)?.checked_mul(self.param(2)?)?;
+                self.write(3, value)?;
+                self.pc += 4;
+            },
+            3 => {
+                if inputs.is_empty() {
+                    return None;
+                }
+                let value = inputs.remove(0);
+                self.write(1, value)?;
+                self.pc += 2;
+            },
+            4 => {
+                outputs.push(self.param(1)?);
+                self.pc += 2;
+            },
+            5 | 6 => {
+                let condition = self.param(1)?;
+                if (condition != 0) == (opcode % 100 == 5) {
+                    let target = self.param(2)?;
+                    if target < 0 {
+                        return None;
+                    }
+                    self.pc = target as usize;
+                } else {
+                    self.pc += 3;
+                }
+            },
+            7 | 8 => {
+                let (left, right) = (self.param(1)?, self.param(2)?);
+                let value = if opcode % 100 == 7 { left < right } else { left == right };
+                self.write(3, value as i64)?;
+                self.pc += 4;
+            },
+            9 => {
+                self.base = self.base.checked_add(self.param(1)?)?;
+                self.pc += 2;
+            },
+            99 => return Some(false),
+            _ => return None
+        }
+        Some(true)
+    }
+
+    fn run(program: &[i64], inputs: &[i64]) -> State {
+        let memory = program.iter().enumerate().map(|(address, &value)| (address as i64, value)).collect();
+        let mut reference = Reference {memory, len: program.len(), pc: 0, base: 0};
+        let mut inputs = inputs.to_vec();
+        let mut outputs = Vec::new();
+        let mut outcome = Outcome::OutOfBudget;
+        for _ in 0..BUDGET {
+            if reference.pc >= reference.len {
+                outcome = Outcome::Halted;
+                break;
+            }
+            match reference.step(&mut inputs, &mut outputs) {
+                Some(true) => continue,
+                Some(false) => outcome = Outcome::Halted,
+                None => outcome = Outcome::Faulted
+            }
+            break;
+        }
+        let cells = reference.memory.len();
+        State {outcome, pc: reference.pc, outputs, memory: reference.memory.into_iter().collect(), cells}
+    }
+}
+
+fn run_intcode<I: Iterator<Item = i64>>(program: &[i64], inputs: &[i64], addresses: I) -> State {
+    let (sender, receiver) = channel();
+    let (output, outputs) = channel();
+    for &value in inputs {
+        sender.send(value).unwrap();
+    }
+    drop(sender);
+    let mut intcode = Intcode::new(program.to_vec(), receiver, output);
+    let mut outcome = Outcome::OutOfBudget;
+    for _ in 0..BUDGET {
+        if !intcode.is_running() {
+            outcome = Outcome::Halted;
+            break;
+        }
+        if intcode.step().is_err() {
+            outcome = Outcome::Faulted;
+            break;
+        }
+    }
+    let memory = addresses.map(|address| (address, intcode.peek(address as usize))).collect();
+    State {outcome, pc: intcode.pc(), outputs: outputs.try_iter().collect(), memory, cells: intcode.memory_size()}
+}
+
+fn instruction(len: usize) -> impl Strategy<Value = Vec<i64>> {
+    let op = prop_oneof![
+        Just((1, 3)), Just((2, 3)), Just((3, 1)), Just((4, 1)), Just((5, 2)),
+        Just((6, 2)), Just((7, 3)), Just((8, 3)), Just((9, 1)), Just((99, 0))
+    ];
+    let param = (0..3i64, -4..len as i64 + 8);
+    (op, prop::collection::vec(param, 3)).prop_map(|((opcode, args_num), params)| {
+        let writes_last = [1, 2, 3, 7, 8].contains(&opcode);
+        let mut code = opcode;
+        let mut words = vec![];
+        for (i, &(mut mode, value)) in params.iter().take(args_num).enumerate() {
+            if writes_last && i == args_num - 1 && mode == 1 {
+                mode = 0;
+            }
+            code += mode * [100, 1000, 10000][i];
+            words.push(value);
+        }
+        let mut result = vec![code];
+        result.extend(words);
+        result
+    })
+}
+
+fn program() -> impl Strategy<Value = Vec<i64>> {
+    (prop::collection::vec(instruction(64), 1..24), prop::collection::vec(-50..50i64, 0..8))
+        .prop_map(|(instructions, data)| {
+            let mut program: Vec<i64> = instructions.into_iter().flatten().collect();
+            program.extend(data);
+            program
+        })
+}
+
+#[test]
+fn matches_reference_far_away() {
+    // Writes 11 near the top of the address space, reads it back and outputs it.
+    let far = i64::MAX - 1;
+    let program = [1101, 5, 6, far, 1, far, 0, far - 1, 4, far - 1, 99];
+    let expected = Reference::run(&program, &[]);
+    assert_eq!(expected.outputs, vec![1112]);
+    assert_eq!(run_intcode(&program, &[], expected.memory.keys().copied()), expected);
+}
+
+proptest! {
+    #[test]
+    fn matches_reference(program in program(), inputs in prop::collection::vec(-100..100i64, 0..8)) {
+        let expected = Reference::run(&program, &inputs);
+        let actual = run_intcode(&program, &inputs, expected.memory.keys().copied());
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn never_panics(program in prop::collection::vec(any::<i64>(), 0..64),
+                    inputs in prop::collection::vec(any::<i64>(), 0..8)) {
+        run_intcode(&program, &inputs, std::iter::empty());
+    }
+
+    #[test]
+    fn never_panics_on_plausible_opcodes(program in prop::collection::vec(-10..22300i64, 0..64),
+                                         inputs in prop::collection::vec(any::<i64>(), 0..8)) {
+        run_intcode(&program, &inputs, std::iter::empty());
+    }
+}
@@ -1,6 +1,11 @@
 use std::error::Error;
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::mpsc::{Sender, Receiver};
+use devices::Device;
+
+pub mod devices;
+pub mod sandbox;
 
 pub type AocResult<T> = std::result::Result<T, Box<dyn Error>>;
 
@@ -15,9 +20,37 @@ pub struct Intcode {
     relative_offset: i64,
     pub input: Receiver<i64>,
     pub output: Sender<i64>,
-    halted: bool
+    halted: bool,
+    history: Option<Vec<Step>>,
+    replay: Vec<i64>,
+    devices: Vec<(usize, Box<dyn Device + Send>)>
+}
+
+/// Undo record of a single executed instruction.
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub pc: usize,
+    pub relative_offset: i64,
+    pub halted: bool,
+    /// Written addresses paired with the values they held before the write.
+    pub writes: Vec<(usize, i64)>,
+    pub input: Option<i64>
 }
 
+/// Returned by `step` when an input instruction finds its input channel closed and empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputExhausted {
+    pub pc: usize
+}
+
+impl fmt::Display for InputExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "No more input at position {}", self.pc)
+    }
+}
+
+impl Error for InputExhausted {}
+
 #[derive(Debug)]
 enum Mode { Address, Immediate, Relative }
 
@@ -40,11 +73,82 @@ impl Intcode {
     pub fn new(program: Vec<i64>, input: Receiver<i64>, output: Sender<i64>) -> Intcode {
         Intcode {
             program, input, output,
-            heap: HashMap::new(), pc: 0, relative_offset: 0, halted: false
+            heap: HashMap::new(), pc: 0, relative_offset: 0, halted: false,
+            history: None, replay: Vec::new(), devices: Vec::new()
+        }
+    }
+
+    /// Maps `device` onto the memory starting at `address`. Instructions are always fetched
+    /// from plain memory, and device writes are not recorded in the undo log.
+    pub fn attach(&mut self, address: usize, device: Box<dyn Device + Send>) -> AocResult<()> {
+        let end = match address.checked_add(device.size()) {
+            Some(end) => end,
+            None => return err!("Device at {} does not fit in memory", address)
+        };
+        for (start, other) in self.devices.iter() {
+            if address < start + other.size() && *start < end {
+                return err!("Device at {}..{} overlaps a device at {}", address, end, start);
+            }
+        }
+        self.devices.push((address, device));
+        Ok(())
+    }
+
+    fn device(&mut self, address: usize) -> Option<(&mut Box<dyn Device + Send>, usize)> {
+        self.devices
+            .iter_mut()
+            .find(|(start, device)| *start <= address && address < start + device.size())
+            .map(|(start, device)| (device, address - *start))
+    }
+
+    /// Starts recording an undo log, which makes `step_back` and `last_writer` available.
+    pub fn enable_history(&mut self) {
+        if self.history.is_none() {
+            self.history = Some(Vec::new());
+        }
+    }
+
+    pub fn history(&self) -> &[Step] {
+        match &self.history {
+            Some(history) => history,
+            None => &[]
+        }
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn relative_offset(&self) -> i64 {
+        self.relative_offset
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.pc < self.program.len() && !self.halted
+    }
+
+    /// Number of memory cells in use: the program itself plus every heap cell written so far.
+    pub fn memory_size(&self) -> usize {
+        self.program.len() + self.heap.len()
+    }
+
+    /// Reads plain memory without touching attached devices.
+    pub fn peek(&self, address: usize) -> i64 {
+        if address < self.program.len() {
+            self.program[address]
+        } else {
+            *self.heap.get(&(address - self.program.len())).unwrap_or(&0)
         }
     }
 
     fn parse(&self) -> AocResult<Instruction> {
+        if !self.is_running() {
+            return err!("Program is not running");
+        }
         let opcode = self.program[self.pc];
         let (op, args_num) = match opcode % 100 {
             1 => (Op::Add, 3),
@@ -62,7 +166,7 @@ impl Intcode {
         let mut modes = opcode / 100;
         let mut args: Vec<Arg> = Vec::new();
         for arg_num in 0..args_num {
-            let value = self.program[self.pc + arg_num + 1];
+            let value = self.peek(self.pc + arg_num + 1);
             let mode = match modes % 10 {
                 0 => Mode::Address,
                 1 => Mode::Immediate,
@@ -70,112 +174,242 @@ impl Intcode {
                 illegal => return err!("Illegal mode '{}'", illegal)
             };
             args.push(Arg {value, mode});
-            modes = modes / 10;
+            modes /= 10;
         }
         Ok(Instruction {op, args})
     }
 
     pub fn exec(&mut self) -> AocResult<()> {
-        while self.pc < self.program.len() && self.halted != true {
-            let instruction = self.parse()?;
-            let len = instruction.args.len();
-            match instruction.op {
-                Op::Halt => self.halt(),
-                Op::Jit => self.jump(&instruction.args, i64::ne),
-                Op::Jif => self.jump(&instruction.args, i64::eq),
-                _ => {
-                    self.pc += len + 1;
-                    match instruction.op {
-                        Op::Add => self.add(&instruction.args),
-                        Op::Mul => self.mul(&instruction.args),
-                        Op::Input => self.input(&instruction.args)?,
-                        Op::Output => self.output(&instruction.args)?,
-                        Op::Lt => self.cmp(&instruction.args, i64::lt),
-                        Op::Eq => self.cmp(&instruction.args, i64::eq),
-                        Op::Rel => self.set_relative_offset(&instruction.args),
-                        _ => unreachable!()
-                    }
+        while self.is_running() {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    pub fn step(&mut self) -> AocResult<()> {
+        let instruction = self.parse()?;
+        if let Some(history) = &mut self.history {
+            history.push(Step {
+                pc: self.pc, relative_offset: self.relative_offset, halted: self.halted,
+                writes: Vec::new(), input: None
+            });
+        }
+        let mut result = self.apply(&instruction);
+        if result.is_ok() {
+            result = self.devices.iter_mut().try_for_each(|(_, device)| device.tick());
+        }
+        if result.is_err() {
+            // Roll back whatever the failed instruction managed to change.
+            self.step_back();
+        }
+        result
+    }
+
+    /// Undoes the last recorded instruction. Outputs that were already sent are not recalled,
+    /// but consumed inputs are delivered again when execution resumes.
+    pub fn step_back(&mut self) -> bool {
+        let step = match self.history.as_mut().and_then(|history| history.pop()) {
+            Some(step) => step,
+            None => return false
+        };
+        for &(address, value) in step.writes.iter().rev() {
+            *self.cell(address) = value;
+        }
+        if let Some(value) = step.input {
+            self.replay.push(value);
+        }
+        self.pc = step.pc;
+        self.relative_offset = step.relative_offset;
+        self.halted = step.halted;
+        true
+    }
+
+    /// Position of the most recent recorded instruction that wrote to `address`.
+    pub fn last_writer(&self, address: usize) -> Option<usize> {
+        self.history()
+            .iter()
+            .rev()
+            .find(|step| step.writes.iter().any(|&(a, _)| a == address))
+            .map(|step| step.pc)
+    }
+
+    fn apply(&mut self, instruction: &Instruction) -> AocResult<()> {
+        let len = instruction.args.len();
+        match instruction.op {
+            Op::Halt => self.halt(),
+            Op::Jit => self.jump(&instruction.args, i64::ne)?,
+            Op::Jif => self.jump(&instruction.args, i64::eq)?,
+            _ => {
+                match instruction.op {
+                    Op::Add => self.arithmetic(&instruction.args, i64::checked_add)?,
+                    Op::Mul => self.arithmetic(&instruction.args, i64::checked_mul)?,
+                    Op::Input => self.input(&instruction.args)?,
+                    Op::Output => self.output(&instruction.args)?,
+                    Op::Lt => self.cmp(&instruction.args, i64::lt)?,
+                    Op::Eq => self.cmp(&instruction.args, i64::eq)?,
+                    Op::Rel => self.set_relative_offset(&instruction.args)?,
+                    _ => unreachable!()
                 }
+                self.pc += len + 1;
             }
         }
         Ok(())
     }
 
-    fn get_value(&mut self, arg: &Arg) -> i64 {
+    fn get_value(&mut self, arg: &Arg) -> AocResult<i64> {
         match arg.mode {
-            Mode::Immediate => arg.value,
-            Mode::Address | Mode::Relative => *self.get_cell(arg)
+            Mode::Immediate => Ok(arg.value),
+            Mode::Address | Mode::Relative => {
+                let address = self.get_address(arg)?;
+                match self.device(address) {
+                    Some((device, offset)) => Ok(device.read(offset)),
+                    None => Ok(self.peek(address))
+                }
+            }
         }
     }
 
-    fn get_cell(&mut self, arg: &Arg) -> &mut i64 {
+    fn get_address(&self, arg: &Arg) -> AocResult<usize> {
         let address = match arg.mode {
-            Mode::Address => arg.value,
-            Mode::Relative => arg.value + self.relative_offset,
-            Mode::Immediate => panic!("Not an address")  // FIXME: remove the panic
+            Mode::Address => Some(arg.value),
+            Mode::Relative => arg.value.checked_add(self.relative_offset),
+            Mode::Immediate => return err!("Immediate argument used as an address at position {}", self.pc)
         };
-        let offset = address - self.program.len() as i64;
-        if offset < 0 {
-            &mut self.program[address as usize]
+        match address {
+            Some(address) if address >= 0 => Ok(address as usize),
+            _ => err!("Invalid address {} + {} at position {}", arg.value, self.relative_offset, self.pc)
+        }
+    }
+
+    fn cell(&mut self, address: usize) -> &mut i64 {
+        if address < self.program.len() {
+            &mut self.program[address]
         } else {
-            self.heap.entry(offset as usize).or_insert(0)
+            self.heap.entry(address - self.program.len()).or_insert(0)
         }
     }
 
-    fn add(&mut self, args: &Vec<Arg>) {
-        let value0 = self.get_value(&args[0]);
-        let value1 = self.get_value(&args[1]);
-        let cell = self.get_cell(&args[2]);
-        *cell = value0 + value1;
+    fn set_cell(&mut self, arg: &Arg, value: i64) -> AocResult<()> {
+        let address = self.get_address(arg)?;
+        if let Some((device, offset)) = self.device(address) {
+            device.write(offset, value);
+            return Ok(());
+        }
+        let cell = self.cell(address);
+        let previous = *cell;
+        *cell = value;
+        if let Some(step) = self.history.as_mut().and_then(|history| history.last_mut()) {
+            step.writes.push((address, previous));
+        }
+        Ok(())
     }
 
-    fn mul(&mut self, args: &Vec<Arg>) {
-        let value0 = self.get_value(&args[0]);
-        let value1 = self.get_value(&args[1]);
-        let cell = self.get_cell(&args[2]);
-        *cell = value0 * value1;
+    fn arithmetic<F>(&mut self, args: &[Arg], op: F) -> AocResult<()>
+        where F: Fn(i64, i64) -> Option<i64>
+    {
+        let value0 = self.get_value(&args[0])?;
+        let value1 = self.get_value(&args[1])?;
+        match op(value0, value1) {
+            Some(value) => self.set_cell(&args[2], value),
+            None => err!("Overflow at position {}", self.pc)
+        }
     }
 
-    fn input(&mut self, args: &Vec<Arg>) -> AocResult<()> {
-        let value = self.input.recv()?;
-        let cell = self.get_cell(&args[0]);
-        *cell = value;
-        Ok(())
+    fn input(&mut self, args: &[Arg]) -> AocResult<()> {
+        let value = match self.replay.pop() {
+            Some(value) => value,
+            None => self.input.recv().map_err(|_| InputExhausted {pc: self.pc})?
+        };
+        if let Some(step) = self.history.as_mut().and_then(|history| history.last_mut()) {
+            step.input = Some(value);
+        }
+        self.set_cell(&args[0], value)
     }
 
-    fn output(&mut self, args: &Vec<Arg>) -> AocResult<()> {
-        let value = self.get_value(&args[0]);
+    fn output(&mut self, args: &[Arg]) -> AocResult<()> {
+        let value = self.get_value(&args[0])?;
         self.output.send(value)?;
         Ok(())
     }
 
-    fn jump<F>(&mut self, args: &Vec<Arg>, cond: F)
+    fn jump<F>(&mut self, args: &[Arg], cond: F) -> AocResult<()>
         where F: Fn(&i64, &i64) -> bool
     {
-        if cond(&self.get_value(&args[0]), &0) {
-            self.pc = self.get_value(&args[1]) as usize;
+        if cond(&self.get_value(&args[0])?, &0) {
+            match self.get_value(&args[1])? {
+                target if target >= 0 => self.pc = target as usize,
+                target => return err!("Invalid jump target {} at position {}", target, self.pc)
+            }
         } else {
             self.pc += args.len() + 1;
         }
+        Ok(())
     }
 
-    fn cmp<F>(&mut self, args: &Vec<Arg>, cmp: F)
+    fn cmp<F>(&mut self, args: &[Arg], cmp: F) -> AocResult<()>
         where F: Fn(&i64, &i64) -> bool
     {
-        let value = match cmp(&self.get_value(&args[0]), &self.get_value(&args[1])) {
+        let value = match cmp(&self.get_value(&args[0])?, &self.get_value(&args[1])?) {
             true => 1,
             false => 0
         };
-        let cell = self.get_cell(&args[2]);
-        *cell = value;
+        self.set_cell(&args[2], value)
     }
 
-    fn set_relative_offset(&mut self, args: &Vec<Arg>) {
-        let value = self.get_value(&args[0]);
-        self.relative_offset += value;
+    fn set_relative_offset(&mut self, args: &[Arg]) -> AocResult<()> {
+        let value = self.get_value(&args[0])?;
+        match self.relative_offset.checked_add(value) {
+            Some(offset) => self.relative_offset = offset,
+            None => return err!("Relative offset overflow at position {}", self.pc)
+        }
+        Ok(())
     }
 
     fn halt(&mut self) {
         self.halted = true;
     }
 }
+
+#[cfg(test)]
+mod conformance;
+
+#[cfg(test)]
+mod differential;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_step_back() -> AocResult<()> {
+        // Reads x, stores x * 3 at 9, outputs it.
+        let program = vec![3, 9, 1002, 9, 3, 9, 4, 9, 99, 0];
+        let (sender, receiver) = channel();
+        let (output, outputs) = channel();
+        sender.send(7)?;
+        let mut intcode = Intcode::new(program.clone(), receiver, output);
+        intcode.enable_history();
+        intcode.exec()?;
+        assert_eq!(intcode.peek(9), 21);
+        assert_eq!(intcode.last_writer(9), Some(2));
+        assert_eq!(intcode.last_writer(8), None);
+        assert_eq!(outputs.recv()?, 21);
+
+        for _ in 0..3 {
+            assert!(intcode.step_back());
+        }
+        assert_eq!(intcode.pc(), 2);
+        assert_eq!(intcode.peek(9), 7);
+        while intcode.step_back() {}
+        assert_eq!(intcode.pc(), 0);
+        assert_eq!(intcode.peek(9), 0);
+
+        // The consumed input is replayed instead of being read from the channel again.
+        intcode.exec()?;
+        assert!(intcode.is_halted());
+        assert_eq!(intcode.peek(9), 21);
+        assert_eq!(outputs.recv()?, 21);
+        Ok(())
+    }
+}
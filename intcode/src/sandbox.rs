@@ -0,0 +1,216 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::channel;
+use crate::{InputExhausted, Intcode};
+
+/// Runs an untrusted program as a function: arguments are fed as inputs, results are collected
+/// from outputs, and the run is cut short instead of hanging or exhausting memory.
+#[derive(Clone)]
+pub struct Sandbox {
+    program: Vec<i64>,
+    budget: usize,
+    memory_cap: usize
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SandboxError {
+    /// The program did not halt within the instruction budget.
+    BudgetExhausted(usize),
+    /// The program touched more memory cells than allowed.
+    MemoryExceeded(usize),
+    /// The program asked for more inputs than it was given.
+    MissingInput,
+    /// The program executed an invalid instruction.
+    Fault(String),
+    /// The outputs do not have the shape of the requested result.
+    UnexpectedOutputs(Vec<i64>)
+}
+
+impl fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SandboxError::BudgetExhausted(budget) => write!(f, "Budget of {} instructions exhausted", budget),
+            SandboxError::MemoryExceeded(cap) => write!(f, "Memory cap of {} cells exceeded", cap),
+            SandboxError::MissingInput => write!(f, "Program requested more inputs than provided"),
+            SandboxError::Fault(message) => write!(f, "Program fault: {}", message),
+            SandboxError::UnexpectedOutputs(outputs) => write!(f, "Unexpected outputs {:?}", outputs)
+        }
+    }
+}
+
+impl Error for SandboxError {}
+
+pub trait Args {
+    fn into_inputs(self) -> Vec<i64>;
+}
+
+pub trait Returns: Sized {
+    fn from_outputs(outputs: &[i64]) -> Option<Self>;
+}
+
+impl Args for () {
+    fn into_inputs(self) -> Vec<i64> {
+        vec![]
+    }
+}
+
+impl Args for i64 {
+    fn into_inputs(self) -> Vec<i64> {
+        vec![self]
+    }
+}
+
+impl Args for (i64, i64) {
+    fn into_inputs(self) -> Vec<i64> {
+        vec![self.0, self.1]
+    }
+}
+
+impl Args for (i64, i64, i64) {
+    fn into_inputs(self) -> Vec<i64> {
+        vec![self.0, self.1, self.2]
+    }
+}
+
+impl Args for &[i64] {
+    fn into_inputs(self) -> Vec<i64> {
+        self.to_vec()
+    }
+}
+
+impl Args for Vec<i64> {
+    fn into_inputs(self) -> Vec<i64> {
+        self
+    }
+}
+
+impl Returns for () {
+    fn from_outputs(outputs: &[i64]) -> Option<()> {
+        match outputs {
+            [] => Some(()),
+            _ => None
+        }
+    }
+}
+
+impl Returns for i64 {
+    fn from_outputs(outputs: &[i64]) -> Option<i64> {
+        match *outputs {
+            [value] => Some(value),
+            _ => None
+        }
+    }
+}
+
+impl Returns for (i64, i64) {
+    fn from_outputs(outputs: &[i64]) -> Option<(i64, i64)> {
+        match *outputs {
+            [first, second] => Some((first, second)),
+            _ => None
+        }
+    }
+}
+
+impl Returns for (i64, i64, i64) {
+    fn from_outputs(outputs: &[i64]) -> Option<(i64, i64, i64)> {
+        match *outputs {
+            [first, second, third] => Some((first, second, third)),
+            _ => None
+        }
+    }
+}
+
+impl Returns for Vec<i64> {
+    fn from_outputs(outputs: &[i64]) -> Option<Vec<i64>> {
+        Some(outputs.to_vec())
+    }
+}
+
+impl Sandbox {
+    pub fn new(program: Vec<i64>) -> Sandbox {
+        Sandbox {program, budget: 1_000_000, memory_cap: 1 << 20}
+    }
+
+    pub fn budget(mut self, instructions: usize) -> Sandbox {
+        self.budget = instructions;
+        self
+    }
+
+    pub fn memory_cap(mut self, cells: usize) -> Sandbox {
+        self.memory_cap = cells;
+        self
+    }
+
+    pub fn call<A: Args, R: Returns>(&self, args: A) -> Result<R, SandboxError> {
+        let outputs = self.run(&args.into_inputs())?;
+        R::from_outputs(&outputs).ok_or(SandboxError::UnexpectedOutputs(outputs))
+    }
+
+    pub fn run(&self, inputs: &[i64]) -> Result<Vec<i64>, SandboxError> {
+        let (sender, receiver) = channel();
+        let (output, outputs) = channel();
+        for &value in inputs {
+            // The receiver is alive until the end of this function.
+            sender.send(value).unwrap();
+        }
+        // With the sender gone, reading past the last input fails instead of blocking.
+        drop(sender);
+
+        let mut intcode = Intcode::new(self.program.clone(), receiver, output);
+        if intcode.memory_size() > self.memory_cap {
+            return Err(SandboxError::MemoryExceeded(self.memory_cap));
+        }
+        for _ in 0..self.budget {
+            if !intcode.is_running() {
+                return Ok(outputs.try_iter().collect());
+            }
+            if let Err(e) = intcode.step() {
+                return match e.downcast_ref::<InputExhausted>() {
+                    Some(_) => Err(SandboxError::MissingInput),
+                    None => Err(SandboxError::Fault(e.to_string()))
+                };
+            }
+            if intcode.memory_size() > self.memory_cap {
+                return Err(SandboxError::MemoryExceeded(self.memory_cap));
+            }
+        }
+        match intcode.is_running() {
+            true => Err(SandboxError::BudgetExhausted(self.budget)),
+            false => Ok(outputs.try_iter().collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUM: [i64; 14] = [3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0];
+
+    #[test]
+    fn test_call() {
+        let sandbox = Sandbox::new(SUM.to_vec());
+        assert_eq!(sandbox.call::<_, i64>((2, 40)), Ok(42));
+        assert_eq!(sandbox.call::<_, Vec<i64>>(vec![1, 2, 3]), Ok(vec![3]));
+        assert_eq!(sandbox.call::<_, i64>(5), Err(SandboxError::MissingInput));
+        assert_eq!(sandbox.call::<_, (i64, i64)>((2, 40)), Err(SandboxError::UnexpectedOutputs(vec![42])));
+    }
+
+    #[test]
+    fn test_limits() {
+        let spin = Sandbox::new(vec![1105, 1, 0]).budget(1000);
+        assert_eq!(spin.call::<_, ()>(()), Err(SandboxError::BudgetExhausted(1000)));
+
+        let hog = Sandbox::new(vec![109, 1, 21101, 0, 0, 100, 1105, 1, 0]).memory_cap(100);
+        assert_eq!(hog.call::<_, ()>(()), Err(SandboxError::MemoryExceeded(100)));
+
+        let fault = Sandbox::new(vec![1101, 1, 1, -1, 99]);
+        assert!(matches!(fault.call::<_, ()>(()), Err(SandboxError::Fault(_))));
+
+        // An input instruction with an invalid address is a fault, not a request for more input.
+        let bad_input = Sandbox::new(vec![203, -1, 99]);
+        assert!(matches!(bad_input.call::<_, ()>(1), Err(SandboxError::Fault(_))));
+
+        assert_eq!(Sandbox::new(SUM.to_vec()).budget(5).call::<_, i64>((1, 1)), Ok(2));
+    }
+}
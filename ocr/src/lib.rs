@@ -0,0 +1,101 @@
+use std::error::Error;
+
+pub type AocResult<T> = std::result::Result<T, Box<dyn Error>>;
+
+macro_rules! err {
+    ($($tt:tt)*) => { Err(Box::<dyn Error>::from(format!($($tt)*))) }
+}
+
+pub const HEIGHT: usize = 6;
+const CELL: usize = 5;
+
+// Letters are four pixels wide and separated by a blank column, except for the wider Y.
+const GLYPHS: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##..", "#..#.", "#..#.", "####.", "#..#.", "#..#."]),
+    ('B', ["###..", "#..#.", "###..", "#..#.", "#..#.", "###.."]),
+    ('C', [".##..", "#..#.", "#....", "#....", "#..#.", ".##.."]),
+    ('E', ["####.", "#....", "###..", "#....", "#....", "####."]),
+    ('F', ["####.", "#....", "###..", "#....", "#....", "#...."]),
+    ('G', [".##..", "#..#.", "#....", "#.##.", "#..#.", ".###."]),
+    ('H', ["#..#.", "#..#.", "####.", "#..#.", "#..#.", "#..#."]),
+    ('J', ["..##.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('K', ["#..#.", "#.#..", "##...", "#.#..", "#.#..", "#..#."]),
+    ('L', ["#....", "#....", "#....", "#....", "#....", "####."]),
+    ('O', [".##..", "#..#.", "#..#.", "#..#.", "#..#.", ".##.."]),
+    ('P', ["###..", "#..#.", "#..#.", "###..", "#....", "#...."]),
+    ('R', ["###..", "#..#.", "#..#.", "###..", "#.#..", "#..#."]),
+    ('S', [".###.", "#....", "#....", ".##..", "...#.", "###.."]),
+    ('U', ["#..#.", "#..#.", "#..#.", "#..#.", "#..#.", ".##.."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####.", "...#.", "..#..", ".#...", "#....", "####."])
+];
+
+fn matches(glyph: &[&str; HEIGHT], rows: &[Vec<bool>], start: usize) -> bool {
+    glyph.iter().zip(rows).all(|(pattern, row)| {
+        pattern.chars().enumerate().all(|(i, ch)| {
+            let lit = *row.get(start + i).unwrap_or(&false);
+            lit == (ch == '#')
+        })
+    })
+}
+
+/// Reads block letters from a six pixel high image. Leading blank columns are skipped.
+pub fn recognize(rows: &[Vec<bool>]) -> AocResult<String> {
+    if rows.len() != HEIGHT {
+        return err!("Expected {} rows, got {}", HEIGHT, rows.len());
+    }
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let is_blank = |x: usize| rows.iter().all(|row| !*row.get(x).unwrap_or(&false));
+    let first = match (0..width).find(|&x| !is_blank(x)) {
+        Some(x) => x,
+        None => return Ok(String::new())
+    };
+
+    let mut result = String::new();
+    for start in (first..width).step_by(CELL) {
+        if (start..start + CELL).all(is_blank) {
+            continue;
+        }
+        match GLYPHS.iter().find(|(_, glyph)| matches(glyph, rows, start)) {
+            Some((letter, _)) => result.push(*letter),
+            None => return err!("Unrecognized glyph at column {}", start)
+        }
+    }
+    Ok(result)
+}
+
+/// Same as `recognize`, for text where `lit` marks set pixels.
+pub fn recognize_str(image: &str, lit: char) -> AocResult<String> {
+    let rows: Vec<Vec<bool>> = image.lines().map(|line| line.chars().map(|ch| ch == lit).collect()).collect();
+    recognize(&rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alphabet() -> AocResult<()> {
+        let image: Vec<String> = (0..HEIGHT)
+            .map(|y| GLYPHS.iter().map(|(_, glyph)| glyph[y]).collect())
+            .collect();
+        assert_eq!(recognize_str(&image.join("\n"), '#')?, "ABCEFGHJKLOPRSUYZ");
+        Ok(())
+    }
+
+    #[test]
+    fn test_recognize() -> AocResult<()> {
+        let image = "\
+            \x20 #  # ####\n\
+            \x20 #  #    #\n\
+            \x20 #  #   #\n\
+            \x20 #  #  #\n\
+            \x20 #  # #\n\
+            \x20  ##  ####";
+        assert_eq!(recognize_str(image, '#')?, "UZ");
+        assert!(recognize_str("#\n#\n#\n#\n#\n#", '#').is_err());
+        assert!(recognize_str("##", '#').is_err());
+        assert_eq!(recognize(&vec![vec![false; 5]; HEIGHT])?, "");
+        Ok(())
+    }
+}